@@ -0,0 +1,24 @@
+use melody_syncer_rust::{
+    collection_handler, convert_album, resolve_spotify_input, SpotifyResourceKind,
+};
+use vercel_runtime::{run, Body, Error, Request, Response};
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+pub async fn handler(req: Request) -> Result<Response<Body>, Error> {
+    collection_handler(
+        req,
+        "Please enter a valid Spotify album ID",
+        |input, params, keys| async move {
+            let album_id = resolve_spotify_input(&input)
+                .await?
+                .id_for(SpotifyResourceKind::Album)?;
+
+            convert_album(&album_id, &keys, &params.options).await
+        },
+    )
+    .await
+}
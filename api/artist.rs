@@ -0,0 +1,27 @@
+use melody_syncer_rust::{
+    collection_handler, convert_artist_top_tracks, parse_market, resolve_spotify_input,
+    SpotifyResourceKind,
+};
+use vercel_runtime::{run, Body, Error, Request, Response};
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+pub async fn handler(req: Request) -> Result<Response<Body>, Error> {
+    collection_handler(
+        req,
+        "Please enter a valid Spotify artist ID",
+        |input, params, keys| async move {
+            let artist_id = resolve_spotify_input(&input)
+                .await?
+                .id_for(SpotifyResourceKind::Artist)?;
+            // Top tracks only exist per market
+            let market = parse_market(params.market.as_deref().unwrap_or("US"))?;
+
+            convert_artist_top_tracks(&artist_id, &market, &keys, &params.options).await
+        },
+    )
+    .await
+}
@@ -0,0 +1,101 @@
+use melody_syncer_rust::{
+    collection_response, conversion_error_response, convert_album, convert_artist_top_tracks,
+    convert_playlist, convert_song, error_response, parse_market, record_conversion,
+    resolve_spotify_input, song_response, CollectionResponse, ConversionParams, ItemStatus,
+    MelodyError, MelodyResult, SongMatch, SpotifyResourceKind, YouTubeKeyPool,
+};
+use serde::Serialize;
+use vercel_runtime::{run, Body, Error, Request, Response};
+
+// What was converted plus the matching pipeline's own response shape
+#[derive(Serialize)]
+struct ConvertResponse<T> {
+    kind: SpotifyResourceKind,
+    #[serde(flatten)]
+    data: T,
+}
+
+enum Converted {
+    Song(SongMatch),
+    Collection(SpotifyResourceKind, CollectionResponse),
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+pub async fn handler(req: Request) -> Result<Response<Body>, Error> {
+    let params = match ConversionParams::from_request(&req) {
+        Ok(params) => params,
+        Err(e) => return error_response(&e),
+    };
+    let keys = params.key_pool();
+
+    match process_input(&params, &keys).await {
+        Ok(Converted::Song(song_match)) => {
+            let response = song_response(song_match, &params, &keys, |data| ConvertResponse {
+                kind: SpotifyResourceKind::Track,
+                data,
+            })?;
+            if response.status().is_success() {
+                record_conversion(1, 0);
+            }
+            Ok(response)
+        }
+        Ok(Converted::Collection(kind, collection)) => {
+            let songs = collection.count(ItemStatus::Matched) as i32;
+            let response = collection_response(collection, &params, &keys, |data| {
+                ConvertResponse { kind, data }
+            })?;
+            if response.status().is_success() {
+                record_conversion(songs, 1);
+            }
+            Ok(response)
+        }
+        Err(e) => conversion_error_response(&e, &params, &keys),
+    }
+}
+
+// Work out what the link points to and hand it to the matching pipeline
+async fn process_input(
+    params: &ConversionParams,
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<Converted> {
+    let input = params.require_input("Please enter a Spotify link or URI")?;
+    let resource = resolve_spotify_input(input).await?;
+    let options = &params.options;
+    let collection = |items| CollectionResponse::new(items, params.give_length);
+
+    match resource.kind {
+        Some(SpotifyResourceKind::Track) => Ok(Converted::Song(
+            convert_song(&resource.id, keys, options).await?,
+        )),
+        Some(SpotifyResourceKind::Playlist) => {
+            let items = convert_playlist(&resource.id, keys, options).await?;
+            Ok(Converted::Collection(
+                SpotifyResourceKind::Playlist,
+                collection(items),
+            ))
+        }
+        Some(SpotifyResourceKind::Album) => {
+            let items = convert_album(&resource.id, keys, options).await?;
+            Ok(Converted::Collection(
+                SpotifyResourceKind::Album,
+                collection(items),
+            ))
+        }
+        // An artist link converts their top tracks - /artist/discography has the rest
+        Some(SpotifyResourceKind::Artist) => {
+            let market = parse_market(params.market.as_deref().unwrap_or("US"))?;
+            let items = convert_artist_top_tracks(&resource.id, &market, keys, options).await?;
+            Ok(Converted::Collection(
+                SpotifyResourceKind::Artist,
+                collection(items),
+            ))
+        }
+        None => Err(MelodyError::InvalidInput(
+            "Please pass a full Spotify link or URI so the type can be detected".to_string(),
+        )),
+    }
+}
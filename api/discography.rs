@@ -0,0 +1,27 @@
+use melody_syncer_rust::{
+    collection_handler, convert_artist_discography, parse_market, resolve_spotify_input,
+    SpotifyResourceKind,
+};
+use vercel_runtime::{run, Body, Error, Request, Response};
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+pub async fn handler(req: Request) -> Result<Response<Body>, Error> {
+    collection_handler(
+        req,
+        "Please enter a valid Spotify artist ID",
+        |input, params, keys| async move {
+            let artist_id = resolve_spotify_input(&input)
+                .await?
+                .id_for(SpotifyResourceKind::Artist)?;
+            // Without a market Spotify returns releases from every market
+            let market = params.market.as_deref().map(parse_market).transpose()?;
+
+            convert_artist_discography(&artist_id, market.as_deref(), &keys, &params.options).await
+        },
+    )
+    .await
+}
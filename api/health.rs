@@ -0,0 +1,32 @@
+use melody_syncer_rust::{json_response, ApiResponse, YOUTUBE_KEY_POOL};
+use serde::Serialize;
+use vercel_runtime::{run, Body, Error, Request, Response, StatusCode};
+
+#[derive(Serialize)]
+struct HealthResponse {
+    keys: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_error: Option<String>,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    run(handler).await
+}
+
+// Which keys are configured, and why none are. This function never calls YouTube itself, so
+// key status, quota and cache counters come from the conversion endpoints (`?health=yes`).
+pub async fn handler(_req: Request) -> Result<Response<Body>, Error> {
+    let config_error = YOUTUBE_KEY_POOL.config_error().map(str::to_string);
+    let status = if config_error.is_some() {
+        StatusCode::SERVICE_UNAVAILABLE
+    } else {
+        StatusCode::OK
+    };
+
+    let response = ApiResponse::success(HealthResponse {
+        keys: YOUTUBE_KEY_POOL.labels(),
+        config_error,
+    });
+    json_response(status, "no-cache", &response)
+}
@@ -13,7 +13,12 @@ pub async fn handler(_req: Request) -> Result<Response<Body>, Error> {
 🚀 Endpoints:
 - GET /song?query={spotify_song_id} - Convert a single Spotify song to YouTube
 - GET /playlist?query={spotify_playlist_id} - Convert entire playlist to YouTube URLs
+- GET /album?query={spotify_album_id} - Convert every track of an album, in order
+- GET /artist?query={spotify_artist_id}&market=US - Convert an artist's top tracks
+- GET /artist/discography?query={spotify_artist_id} - Convert every album and single track of an artist, without duplicates
+- GET /convert?query={spotify_link} - Convert any Spotify track, album, playlist or artist link
 - GET /analytics - Get usage statistics
+- GET /health - Labels of the configured YouTube API keys. Add health=yes to any conversion for live key health
 - GET /help - This help page
 - GET/POST /repeat - Utility endpoint to repeat text multiple times
 
@@ -22,8 +27,10 @@ pub async fn handler(_req: Request) -> Result<Response<Body>, Error> {
 - Connection pooling for maximum speed
 - Token caching for Spotify API
 - Multiple YouTube API key support with failover
+- Quota-aware key pool that parks exhausted keys until the daily reset
 - Parallel processing for playlists
 - Custom accuracy scoring algorithm
+- Match cache, so songs converted before skip the YouTube search
 
 🔑 Authentication:
 You can provide YouTube API key in two ways:
@@ -35,7 +42,15 @@ If no API key provided, server uses default (limited quota).
 📖 Usage Examples:
 - Single song: /song?query=58ge6dfP91o9oXMzq3XkIS
 - Playlist: /playlist?query=7fITt66rmO4QIeNs2LPRDj
+- Playlist, keeping matches when some searches fail: /playlist?query=PLAYLIST_ID&partial=yes
 - With API key: /song?query=SONG_ID&youtubeAPIKEY=YOUR_KEY
+- Top 3 alternatives with confidence: /song?query=SONG_ID&candidates=3
+- Why a song matched the way it did: /song?query=SONG_ID&explain=true
+- The music video instead of the audio: /song?query=SONG_ID&prefer=video
+- Only videos playable in Germany: /playlist?query=PLAYLIST_ID&region=DE
+- Any link: /convert?query=https://open.spotify.com/playlist/7fITt66rmO4QIeNs2LPRDj
+
+IDs, open.spotify.com links, spotify: URIs and spotify.link short links are all accepted.
 
 💻 GitHub: https://git.new/melodysyncer
 📚 Full docs: /docs
@@ -0,0 +1,228 @@
+use crate::{get_mongo_client, LruCache};
+use async_trait::async_trait;
+use mongodb::{
+    bson::{doc, Document},
+    options::UpdateOptions,
+    Collection,
+};
+use once_cell::sync::Lazy;
+use std::{
+    env,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// Thirty days - long enough to matter, short enough to pick up better uploads eventually
+const DEFAULT_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+// Most matches one instance keeps in memory
+const MEMORY_CAPACITY: usize = 10_000;
+
+const DEFAULT_COLLECTION: &str = "match_cache";
+
+// Cache every conversion consults first, picked once per instance from MELODY_MATCH_CACHE.
+// None when caching is turned off.
+pub static MATCH_CACHE: Lazy<Option<Arc<dyn MatchCache>>> = Lazy::new(|| {
+    let ttl = env::var("MELODY_MATCH_CACHE_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_TTL);
+
+    // MongoDB when the deployment has one, so matches outlive the instance
+    let has_mongo = env::var("MONGO_URI").is_ok()
+        && env::var("MONGO_DB").is_ok_and(|database| !database.is_empty());
+    let backend = env::var("MELODY_MATCH_CACHE").unwrap_or_else(|_| {
+        if has_mongo {
+            "mongodb".to_string()
+        } else {
+            "memory".to_string()
+        }
+    });
+
+    match backend.to_lowercase().as_str() {
+        "off" | "none" => None,
+        "mongodb" if has_mongo => Some(Arc::new(LayeredMatchCache::new(
+            MemoryMatchCache::new(ttl, MEMORY_CAPACITY),
+            MongoMatchCache::from_env(ttl),
+        )) as Arc<dyn MatchCache>),
+        "mongodb" => {
+            // Without a database every lookup would miss - keep matches in memory instead
+            eprintln!("MELODY_MATCH_CACHE=mongodb needs MONGO_URI and MONGO_DB, using memory");
+            Some(Arc::new(MemoryMatchCache::new(ttl, MEMORY_CAPACITY)) as Arc<dyn MatchCache>)
+        }
+        _ => Some(Arc::new(MemoryMatchCache::new(ttl, MEMORY_CAPACITY)) as Arc<dyn MatchCache>),
+    }
+});
+
+// The video a Spotify track was matched to, and how sure we were
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedMatch {
+    pub video_id: String,
+    pub score: f64,
+    pub confidence: f64,
+    // Seconds since the Unix epoch
+    pub cached_at: u64,
+}
+
+impl CachedMatch {
+    pub fn new(video_id: &str, score: f64, confidence: f64) -> Self {
+        Self {
+            video_id: video_id.to_string(),
+            score,
+            confidence,
+            cached_at: unix_now(),
+        }
+    }
+
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        unix_now().saturating_sub(self.cached_at) < ttl.as_secs()
+    }
+}
+
+// Matches keyed by Spotify track ID. A cache that cannot be reached behaves as empty -
+// it must never fail a conversion.
+#[async_trait]
+pub trait MatchCache: Send + Sync {
+    // None on a miss or when the entry has expired
+    async fn get(&self, key: &str) -> Option<CachedMatch>;
+
+    async fn put(&self, key: &str, entry: CachedMatch);
+}
+
+// Per-instance cache, lost on cold starts
+pub struct MemoryMatchCache {
+    ttl: Duration,
+    entries: LruCache<String, CachedMatch>,
+}
+
+impl MemoryMatchCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            entries: LruCache::new(capacity, ttl),
+        }
+    }
+}
+
+#[async_trait]
+impl MatchCache for MemoryMatchCache {
+    async fn get(&self, key: &str) -> Option<CachedMatch> {
+        // The LRU ages entries from when they were put here, which may be later than the match
+        self.entries
+            .get(key)
+            .filter(|entry| entry.is_fresh(self.ttl))
+    }
+
+    async fn put(&self, key: &str, entry: CachedMatch) {
+        self.entries.insert(key.to_string(), entry);
+    }
+}
+
+// This instance's memory in front of a shared cache, so a repeat on a warm instance makes no
+// round trip. Shared hits are copied into memory on the way out.
+pub struct LayeredMatchCache {
+    local: MemoryMatchCache,
+    shared: Box<dyn MatchCache>,
+}
+
+impl LayeredMatchCache {
+    pub fn new(local: MemoryMatchCache, shared: impl MatchCache + 'static) -> Self {
+        Self {
+            local,
+            shared: Box::new(shared),
+        }
+    }
+}
+
+#[async_trait]
+impl MatchCache for LayeredMatchCache {
+    async fn get(&self, key: &str) -> Option<CachedMatch> {
+        if let Some(entry) = self.local.get(key).await {
+            return Some(entry);
+        }
+
+        let entry = self.shared.get(key).await?;
+        self.local.put(key, entry.clone()).await;
+        Some(entry)
+    }
+
+    async fn put(&self, key: &str, entry: CachedMatch) {
+        self.local.put(key, entry.clone()).await;
+        self.shared.put(key, entry).await;
+    }
+}
+
+// Shared by every instance, in the deployment's MongoDB (MONGO_URI / MONGO_DB)
+pub struct MongoMatchCache {
+    ttl: Duration,
+    database: String,
+    collection: String,
+}
+
+impl MongoMatchCache {
+    // MELODY_MATCH_CACHE_COLLECTION names the collection, "match_cache" by default
+    pub fn from_env(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            database: env::var("MONGO_DB").unwrap_or_default(),
+            collection: env::var("MELODY_MATCH_CACHE_COLLECTION")
+                .unwrap_or_else(|_| DEFAULT_COLLECTION.to_string()),
+        }
+    }
+
+    async fn collection(&self) -> Option<Collection<Document>> {
+        if self.database.is_empty() {
+            return None;
+        }
+        let client = get_mongo_client().await?;
+        Some(client.database(&self.database).collection(&self.collection))
+    }
+}
+
+#[async_trait]
+impl MatchCache for MongoMatchCache {
+    async fn get(&self, key: &str) -> Option<CachedMatch> {
+        let collection = self.collection().await?;
+        let document = collection.find_one(doc! { "_id": key }, None).await.ok()??;
+
+        let entry = CachedMatch {
+            video_id: document.get_str("video_id").ok()?.to_string(),
+            score: document.get_f64("score").ok()?,
+            confidence: document.get_f64("confidence").ok()?,
+            cached_at: u64::try_from(document.get_i64("cached_at").ok()?).ok()?,
+        };
+        entry.is_fresh(self.ttl).then_some(entry)
+    }
+
+    async fn put(&self, key: &str, entry: CachedMatch) {
+        let Some(collection) = self.collection().await else {
+            return;
+        };
+
+        let update = doc! {
+            "$set": {
+                "video_id": entry.video_id,
+                "score": entry.score,
+                "confidence": entry.confidence,
+                "cached_at": i64::try_from(entry.cached_at).unwrap_or(i64::MAX),
+            }
+        };
+        let options = UpdateOptions::builder().upsert(true).build();
+        if collection
+            .update_one(doc! { "_id": key }, update, options)
+            .await
+            .is_err()
+        {
+            // A lost write only costs a search next time
+            eprintln!("Match cache update failed");
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
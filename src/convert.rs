@@ -0,0 +1,819 @@
+use crate::{
+    build_search_query, get_song_info, match_scorer, no_artists_error, normalize_title,
+    parse_market, search_track_candidates, spotify_get_cached, CachedMatch, Candidate,
+    MatchPreference, MatchThreshold, MelodyError, MelodyResult, SearchOptions, SpotifyAlbum,
+    SpotifyArtist, SpotifyExternalIds, TrackQuery, YouTubeKeyPool, MATCH_CACHE, MATCH_THRESHOLD,
+    MAX_CANDIDATES, SCORING_FINGERPRINT,
+};
+use futures::{
+    future::join_all,
+    stream::{self, StreamExt},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, future::Future};
+
+// Spotify's "get several albums" endpoint takes at most 20 IDs
+const MAX_ALBUM_IDS_PER_REQUEST: usize = 20;
+
+// Same song released twice (album cut vs single) rarely differs by more than this
+const DUPLICATE_DURATION_TOLERANCE_MS: u32 = 2_000;
+
+// Track searches one collection runs at once. Each costs 100 quota units, so a discography of
+// hundreds of tracks must not fire them all together and drain every key before one fails.
+const MAX_CONCURRENT_SEARCHES: usize = 8;
+
+#[derive(Deserialize)]
+struct SpotifyPlaylistResponse {
+    items: Vec<SpotifyPlaylistItem>,
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyPlaylistItem {
+    #[serde(default)]
+    is_local: bool,
+    track: Option<SpotifyTrack>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyAlbumResponse {
+    name: String,
+    tracks: SpotifyAlbumTracksPage,
+}
+
+#[derive(Deserialize)]
+struct SpotifyAlbumTracksPage {
+    items: Vec<SpotifyAlbumTrack>,
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SpotifySeveralAlbumsResponse {
+    albums: Vec<Option<SpotifyAlbumResponse>>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyTopTracksResponse {
+    tracks: Vec<SpotifyTrack>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyArtistAlbumsPage {
+    items: Vec<SpotifyArtistAlbum>,
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SpotifyArtistAlbum {
+    id: String,
+}
+
+// Album tracks are "simplified" - no album object, but they know their position
+#[derive(Deserialize)]
+struct SpotifyAlbumTrack {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    artists: Vec<SpotifyArtist>,
+    duration_ms: u32,
+    track_number: u32,
+}
+
+// Episodes come back through the same endpoint, so everything but the name is optional
+#[derive(Deserialize)]
+struct SpotifyTrack {
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    artists: Vec<SpotifyArtist>,
+    album: Option<SpotifyAlbum>,
+    #[serde(default)]
+    duration_ms: u32,
+    #[serde(rename = "type", default)]
+    kind: Option<String>,
+    #[serde(default)]
+    episode: bool,
+    #[serde(default)]
+    external_ids: SpotifyExternalIds,
+}
+
+// Per-request knobs shared by every conversion
+#[derive(Debug, Clone)]
+pub struct MatchOptions {
+    // How many scored alternatives to return next to each match, 0 for none
+    pub candidates: usize,
+    // Return the search query and every candidate's per-signal score breakdown
+    pub explain: bool,
+    // Below this the best candidate is reported as no_match instead of linked
+    pub threshold: MatchThreshold,
+    // Only match videos watchable in this country, two-letter code
+    pub region: Option<String>,
+    // Music video, audio-only upload, or whichever matches best
+    pub prefer: MatchPreference,
+}
+
+impl Default for MatchOptions {
+    fn default() -> Self {
+        Self {
+            candidates: 0,
+            explain: false,
+            threshold: *MATCH_THRESHOLD,
+            region: None,
+            prefer: MatchPreference::Any,
+        }
+    }
+}
+
+impl MatchOptions {
+    // `?candidates=N` - more than a search returns is capped, anything but a number is a 400
+    pub fn with_candidates(mut self, value: &str) -> MelodyResult<Self> {
+        let candidates = value.parse::<usize>().map_err(|_| {
+            MelodyError::InvalidInput(format!(
+                "'{}' is not a valid candidates value - use a number from 0 to {}",
+                value, MAX_CANDIDATES
+            ))
+        })?;
+        self.candidates = candidates.min(MAX_CANDIDATES);
+        Ok(self)
+    }
+
+    // `?explain=true`
+    pub fn with_explain(mut self, value: &str) -> Self {
+        self.explain = value == "true" || value == "yes";
+        self
+    }
+
+    // `?min_confidence=0.4` - overrides the server default for this request, anything but a
+    // number is a 400
+    pub fn with_min_confidence(mut self, value: &str) -> MelodyResult<Self> {
+        let min_confidence = value
+            .parse::<f64>()
+            .ok()
+            .filter(|min_confidence| min_confidence.is_finite())
+            .ok_or_else(|| {
+                MelodyError::InvalidInput(format!(
+                    "'{}' is not a valid min_confidence - use a number from 0 to 1",
+                    value
+                ))
+            })?;
+        self.threshold.min_confidence = min_confidence.clamp(0.0, 1.0);
+        Ok(self)
+    }
+
+    // `?region=DE` - checked by `validate` so a typo is a 400 rather than an empty match list
+    pub fn with_region(mut self, value: &str) -> Self {
+        self.region = (!value.is_empty()).then(|| value.to_ascii_uppercase());
+        self
+    }
+
+    // `?prefer=audio|video|any` - anything else is a 400, like an invalid region
+    pub fn with_prefer(mut self, value: &str) -> MelodyResult<Self> {
+        self.prefer = MatchPreference::parse(value).ok_or_else(|| {
+            MelodyError::InvalidInput(format!(
+                "'{}' is not a valid prefer value - use audio, video or any",
+                value
+            ))
+        })?;
+        Ok(self)
+    }
+
+    pub fn validate(&self) -> MelodyResult<()> {
+        if let Some(region) = &self.region {
+            parse_market(region)?;
+        }
+        Ok(())
+    }
+
+    // Scoring profile, region and preference can change the pick, so each combination is
+    // cached on its own
+    fn cache_key(&self, spotify_id: &str) -> String {
+        let mut key = format!("{}:{}", spotify_id, *SCORING_FINGERPRINT);
+        if self.prefer != MatchPreference::Any {
+            key.push_str(&format!(":{}", self.prefer.as_str()));
+        }
+        if let Some(region) = &self.region {
+            key.push_str(&format!(":{}", region));
+        }
+        key
+    }
+
+    // Explaining a match without its competitors would not explain much
+    fn candidate_limit(&self) -> usize {
+        if self.explain && self.candidates == 0 {
+            MAX_CANDIDATES
+        } else {
+            self.candidates
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Matched,
+    Unavailable,
+    LocalFile,
+    Episode,
+    SearchFailed,
+    // Searched fine, but no candidate reached the match threshold
+    NoMatch,
+}
+
+// Why a search_failed item failed, so clients know whether a retry can help
+#[derive(Debug, Serialize)]
+pub struct ItemError {
+    pub code: &'static str,
+    pub message: String,
+    #[serde(skip)]
+    pub status_code: u16,
+}
+
+impl From<MelodyError> for ItemError {
+    fn from(e: MelodyError) -> Self {
+        Self {
+            code: e.code(),
+            message: e.user_message(),
+            status_code: e.status_code(),
+        }
+    }
+}
+
+// One entry of a converted collection, at the same position as in Spotify
+#[derive(Debug, Serialize)]
+pub struct ConvertedItem {
+    pub status: ItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spotify_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<Vec<Candidate>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_query: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ItemError>,
+}
+
+impl ConvertedItem {
+    fn skipped(status: ItemStatus, spotify_id: Option<String>, name: Option<String>) -> Self {
+        Self {
+            status,
+            url: None,
+            spotify_id,
+            name,
+            isrc: None,
+            candidates: None,
+            search_query: None,
+            error: None,
+        }
+    }
+
+    fn failed(spotify_id: String, query: TrackQuery, error: MelodyError) -> Self {
+        Self {
+            isrc: query.isrc,
+            error: Some(error.into()),
+            ..Self::skipped(ItemStatus::SearchFailed, Some(spotify_id), Some(query.name))
+        }
+    }
+}
+
+// Response body shared by every endpoint that converts a list of tracks
+#[derive(Serialize)]
+pub struct CollectionResponse {
+    // One entry per item, null where nothing was matched
+    pub list: Vec<Option<String>>,
+    pub items: Vec<ConvertedItem>,
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+}
+
+// How a converted collection should be reported
+pub enum CollectionOutcome<'a> {
+    // Every searchable item was matched
+    Complete,
+    // Some searches failed but the rest is worth returning
+    Partial,
+    // Fail the whole request with this item's error
+    Failed(&'a ItemError),
+}
+
+impl CollectionResponse {
+    pub fn new(items: Vec<ConvertedItem>, give_length: bool) -> Self {
+        let list: Vec<_> = items.iter().map(|item| item.url.clone()).collect();
+        Self {
+            length: give_length.then_some(list.len()),
+            total: items.len(),
+            list,
+            items,
+        }
+    }
+
+    pub fn count(&self, status: ItemStatus) -> usize {
+        self.items.iter().filter(|item| item.status == status).count()
+    }
+
+    // Without partial mode a single failed search fails the whole collection.
+    // In partial mode we only bail out when nothing could be matched at all.
+    pub fn outcome(&self, partial: bool) -> CollectionOutcome<'_> {
+        let first_error = self.items.iter().find_map(|item| item.error.as_ref());
+
+        match first_error {
+            None => CollectionOutcome::Complete,
+            Some(_) if partial && self.count(ItemStatus::Matched) > 0 => CollectionOutcome::Partial,
+            Some(error) => CollectionOutcome::Failed(error),
+        }
+    }
+}
+
+// Response body of a single converted song. Without a url it is the data of a no_match error,
+// so explain and candidates still show why nothing was picked.
+#[derive(Debug, Serialize)]
+pub struct SongMatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub isrc: Option<String>,
+    // Only when alternatives were asked for - the first one is the match itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub candidates: Option<Vec<Candidate>>,
+    // Only when explaining - what was sent to YouTube
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_query: Option<String>,
+    // Of the best candidate, 0 when there was none
+    #[serde(skip)]
+    pub confidence: f64,
+}
+
+impl SongMatch {
+    // The error to answer with when no candidate was good enough
+    pub fn no_match_error(&self) -> Option<MelodyError> {
+        self.url
+            .is_none()
+            .then_some(MelodyError::NoMatch(self.confidence))
+    }
+}
+
+pub fn youtube_url(video_id: &str) -> String {
+    format!("https://www.youtube.com/watch?v={}", video_id)
+}
+
+// Spotify track ID -> YouTube URL. A track nothing matched well enough comes back without a url,
+// see SongMatch::no_match_error.
+pub async fn convert_song(
+    song_id: &str,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<SongMatch> {
+    options.validate()?;
+    // Fetch song info from Spotify
+    let song = get_song_info(song_id).await?;
+    let query = TrackQuery {
+        prefer: options.prefer,
+        ..TrackQuery::from_song(&song)?
+    };
+
+    // Search YouTube for the best match
+    let best = search_best(song_id, &query, keys, options).await?;
+
+    Ok(SongMatch {
+        url: best.video_id.as_deref().map(youtube_url),
+        isrc: query.isrc,
+        candidates: best.candidates,
+        search_query: best.search_query,
+        confidence: best.best_confidence,
+    })
+}
+
+// The pick, plus whatever the caller asked to see about how it was made
+struct BestMatch {
+    // None when the best candidate is below the threshold
+    video_id: Option<String>,
+    best_confidence: f64,
+    candidates: Option<Vec<Candidate>>,
+    search_query: Option<String>,
+}
+
+// Cached match first, a YouTube search on a miss
+async fn search_best(
+    spotify_id: &str,
+    query: &TrackQuery,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<BestMatch> {
+    let limit = options.candidate_limit();
+    let cache = MATCH_CACHE.as_deref();
+    let cache_key = options.cache_key(spotify_id);
+
+    // Alternatives and explanations need the full search, a cached pick has neither
+    if let (Some(cache), 0) = (cache, limit) {
+        if let Some(hit) = cache.get(&cache_key).await {
+            if options.threshold.accepts(hit.score, hit.confidence) {
+                return Ok(BestMatch {
+                    video_id: Some(hit.video_id),
+                    best_confidence: hit.confidence,
+                    candidates: None,
+                    search_query: None,
+                });
+            }
+        }
+    }
+
+    let scorer = match_scorer()?;
+    let search_options = SearchOptions {
+        limit,
+        region: options.region.clone(),
+    };
+    let mut candidates =
+        search_track_candidates(query, keys, scorer.as_ref(), &search_options).await?;
+
+    // Nothing playable came back - as good as nothing matching
+    let (video_id, best_confidence) = match candidates.first() {
+        Some(best) => (
+            options
+                .threshold
+                .accepts(best.score, best.confidence)
+                .then(|| best.video_id.clone()),
+            best.confidence,
+        ),
+        None => (None, 0.0),
+    };
+
+    if let (Some(cache), Some(video_id), Some(best)) = (cache, &video_id, candidates.first()) {
+        cache
+            .put(&cache_key, CachedMatch::new(video_id, best.score, best.confidence))
+            .await;
+    }
+
+    if !options.explain {
+        for candidate in &mut candidates {
+            candidate.signals = None;
+        }
+    }
+
+    Ok(BestMatch {
+        video_id,
+        best_confidence,
+        candidates: (limit > 0).then_some(candidates),
+        search_query: options.explain.then(|| build_search_query(query)),
+    })
+}
+
+// Spotify playlist ID -> one ConvertedItem per playlist item
+pub async fn convert_playlist(
+    playlist_id: &str,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<Vec<ConvertedItem>> {
+    options.validate()?;
+    // Fetch every page of the playlist - Spotify caps each page at 100 items
+    let mut items = Vec::new();
+    let mut next_url = Some(format!(
+        "https://api.spotify.com/v1/playlists/{}/tracks?limit=100",
+        playlist_id
+    ));
+
+    while let Some(url) = next_url {
+        let page: SpotifyPlaylistResponse = spotify_get_cached(&url, "Playlist").await?;
+        items.extend(page.items);
+        next_url = page.next;
+    }
+
+    if items.is_empty() {
+        return Err(MelodyError::Empty("This playlist is empty".to_string()));
+    }
+
+    // Every item gets a result so the output lines up with the Spotify playlist
+    let search_tasks: Vec<_> = items
+        .into_iter()
+        .map(|item| convert_playlist_item(item, keys, options))
+        .collect();
+
+    Ok(convert_in_order(search_tasks).await)
+}
+
+async fn convert_playlist_item(
+    item: SpotifyPlaylistItem,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> ConvertedItem {
+    let track = match item.track {
+        Some(track) => track,
+        None => return ConvertedItem::skipped(ItemStatus::Unavailable, None, None),
+    };
+
+    if item.is_local {
+        return ConvertedItem::skipped(ItemStatus::LocalFile, None, Some(track.name));
+    }
+
+    convert_full_track(track, keys, options).await
+}
+
+// Full track objects (playlists, top tracks) carry their own album
+async fn convert_full_track(
+    track: SpotifyTrack,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> ConvertedItem {
+    if track.episode || track.kind.as_deref() == Some("episode") {
+        return ConvertedItem::skipped(ItemStatus::Episode, track.id, Some(track.name));
+    }
+
+    let (id, query) = match (track.id, track.album) {
+        (Some(id), Some(album)) => (
+            id,
+            TrackQuery {
+                isrc: track.external_ids.isrc,
+                prefer: options.prefer,
+                ..TrackQuery::new(&track.name, &track.artists, &album.name, track.duration_ms)
+            },
+        ),
+        (id, _) => return ConvertedItem::skipped(ItemStatus::Unavailable, id, Some(track.name)),
+    };
+
+    convert_track(id, query, keys, options).await
+}
+
+// Spotify album ID -> one ConvertedItem per album track, in album order
+pub async fn convert_album(
+    album_id: &str,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<Vec<ConvertedItem>> {
+    options.validate()?;
+    let album: SpotifyAlbumResponse = spotify_get_cached(
+        &format!("https://api.spotify.com/v1/albums/{}", album_id),
+        "Album",
+    )
+    .await?;
+    let (album_name, tracks) = fetch_album_tracks(album).await?;
+
+    if tracks.is_empty() {
+        return Err(MelodyError::Empty("This album is empty".to_string()));
+    }
+
+    let search_tasks: Vec<_> = tracks
+        .into_iter()
+        .map(|track| convert_album_track(track, &album_name, keys, options))
+        .collect();
+
+    Ok(convert_in_order(search_tasks).await)
+}
+
+// Spotify artist ID -> the artist's top tracks in `market`, in Spotify's order
+pub async fn convert_artist_top_tracks(
+    artist_id: &str,
+    market: &str,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<Vec<ConvertedItem>> {
+    options.validate()?;
+    let response: SpotifyTopTracksResponse = spotify_get_cached(
+        &format!(
+            "https://api.spotify.com/v1/artists/{}/top-tracks?market={}",
+            artist_id, market
+        ),
+        "Artist",
+    )
+    .await?;
+
+    if response.tracks.is_empty() {
+        return Err(MelodyError::Empty(format!(
+            "This artist has no top tracks in market {}",
+            market
+        )));
+    }
+
+    let search_tasks: Vec<_> = response
+        .tracks
+        .into_iter()
+        .map(|track| convert_full_track(track, keys, options))
+        .collect();
+
+    Ok(convert_in_order(search_tasks).await)
+}
+
+// Spotify artist ID -> every track on the artist's albums and singles, duplicates removed.
+// Albums come first, so a single that also appears on an album resolves to the album cut.
+pub async fn convert_artist_discography(
+    artist_id: &str,
+    market: Option<&str>,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> MelodyResult<Vec<ConvertedItem>> {
+    options.validate()?;
+    let mut album_ids = Vec::new();
+    let mut next_url = Some(format!(
+        "https://api.spotify.com/v1/artists/{}/albums?include_groups=album,single&limit=50{}",
+        artist_id,
+        market.map(|m| format!("&market={}", m)).unwrap_or_default()
+    ));
+
+    while let Some(url) = next_url {
+        let page: SpotifyArtistAlbumsPage = spotify_get_cached(&url, "Artist").await?;
+        album_ids.extend(page.items.into_iter().map(|album| album.id));
+        next_url = page.next;
+    }
+
+    // Full albums in batches of 20, each batch fetched in parallel
+    let album_tasks: Vec<_> = album_ids
+        .chunks(MAX_ALBUM_IDS_PER_REQUEST)
+        .map(|chunk| async move {
+            let url = format!("https://api.spotify.com/v1/albums?ids={}", chunk.join(","));
+            let response: SpotifySeveralAlbumsResponse = spotify_get_cached(&url, "Album").await?;
+            let mut albums = Vec::new();
+            for album in response.albums.into_iter().flatten() {
+                albums.push(fetch_album_tracks(album).await?);
+            }
+            Ok::<_, MelodyError>(albums)
+        })
+        .collect();
+
+    let mut tracks = Vec::new();
+    for albums in join_all(album_tasks).await {
+        for (album_name, album_tracks) in albums? {
+            tracks.extend(album_tracks.into_iter().map(|track| (album_name.clone(), track)));
+        }
+    }
+
+    let tracks = dedupe_tracks(tracks);
+    if tracks.is_empty() {
+        return Err(MelodyError::Empty(
+            "This artist has no albums or singles".to_string(),
+        ));
+    }
+
+    let search_tasks: Vec<_> = tracks
+        .into_iter()
+        .map(|(album_name, track)| async move {
+            convert_album_track(track, &album_name, keys, options).await
+        })
+        .collect();
+
+    Ok(convert_in_order(search_tasks).await)
+}
+
+// Run the searches a few at a time, keeping the collection's order
+async fn convert_in_order<Fut>(search_tasks: Vec<Fut>) -> Vec<ConvertedItem>
+where
+    Fut: Future<Output = ConvertedItem>,
+{
+    stream::iter(search_tasks)
+        .buffered(MAX_CONCURRENT_SEARCHES)
+        .collect()
+        .await
+}
+
+// Keep the first of every group of tracks with the same name and (nearly) the same length.
+// Names are compared normalized, so "Song" and "Song - Remastered 2011" are one track.
+fn dedupe_tracks(tracks: Vec<(String, SpotifyAlbumTrack)>) -> Vec<(String, SpotifyAlbumTrack)> {
+    let mut seen: HashMap<String, Vec<u32>> = HashMap::new();
+
+    tracks
+        .into_iter()
+        .filter(|(_, track)| {
+            // Names made only of punctuation normalize to nothing - compare those as written
+            let mut name = normalize_title(&track.name);
+            if name.is_empty() {
+                name = track.name.trim().to_lowercase();
+            }
+            let durations = seen.entry(name).or_default();
+            let duplicate = durations
+                .iter()
+                .any(|&d| d.abs_diff(track.duration_ms) <= DUPLICATE_DURATION_TOLERANCE_MS);
+            if !duplicate {
+                durations.push(track.duration_ms);
+            }
+            !duplicate
+        })
+        .collect()
+}
+
+// The album object carries the first page of tracks, the rest is paginated (50 per page)
+async fn fetch_album_tracks(
+    album: SpotifyAlbumResponse,
+) -> MelodyResult<(String, Vec<SpotifyAlbumTrack>)> {
+    let mut tracks = album.tracks.items;
+    let mut next_url = album.tracks.next;
+    while let Some(url) = next_url {
+        let page: SpotifyAlbumTracksPage = spotify_get_cached(&url, "Album").await?;
+        tracks.extend(page.items);
+        next_url = page.next;
+    }
+
+    Ok((album.name, tracks))
+}
+
+async fn convert_album_track(
+    track: SpotifyAlbumTrack,
+    album_name: &str,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> ConvertedItem {
+    let Some(id) = track.id else {
+        return ConvertedItem::skipped(ItemStatus::Unavailable, None, Some(track.name));
+    };
+
+    // Album name and track order act as extra matching signals
+    let query = TrackQuery {
+        album_position: Some(track.track_number),
+        prefer: options.prefer,
+        ..TrackQuery::new(&track.name, &track.artists, album_name, track.duration_ms)
+    };
+    convert_track(id, query, keys, options).await
+}
+
+// Search one track and wrap the outcome as a collection entry
+async fn convert_track(
+    spotify_id: String,
+    query: TrackQuery,
+    keys: &YouTubeKeyPool,
+    options: &MatchOptions,
+) -> ConvertedItem {
+    // Same `empty` error /song gives, rather than a search that cannot work
+    if query.artists.is_empty() {
+        return ConvertedItem::failed(spotify_id, query, no_artists_error());
+    }
+
+    match search_best(&spotify_id, &query, keys, options).await {
+        Ok(best) => ConvertedItem {
+            status: if best.video_id.is_some() {
+                ItemStatus::Matched
+            } else {
+                ItemStatus::NoMatch
+            },
+            url: best.video_id.as_deref().map(youtube_url),
+            spotify_id: Some(spotify_id),
+            name: Some(query.name),
+            isrc: query.isrc,
+            candidates: best.candidates,
+            search_query: best.search_query,
+            error: None,
+        },
+        Err(e) => ConvertedItem::failed(spotify_id, query, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(name: &str, duration_ms: u32) -> (String, SpotifyAlbumTrack) {
+        let track = SpotifyAlbumTrack {
+            id: Some(format!("{}-{}", name, duration_ms)),
+            name: name.to_string(),
+            artists: Vec::new(),
+            duration_ms,
+            track_number: 1,
+        };
+        ("Album".to_string(), track)
+    }
+
+    fn names(tracks: &[(String, SpotifyAlbumTrack)]) -> Vec<&str> {
+        tracks.iter().map(|(_, track)| track.name.as_str()).collect()
+    }
+
+    #[test]
+    fn keeps_the_first_of_each_song() {
+        let tracks = dedupe_tracks(vec![
+            track("Song", 200_000),
+            track("Other Song", 180_000),
+            track("Song", 201_500),
+        ]);
+
+        assert_eq!(names(&tracks), ["Song", "Other Song"]);
+    }
+
+    #[test]
+    fn compares_names_normalized() {
+        let tracks = dedupe_tracks(vec![
+            track("Song", 200_000),
+            track("Song - Remastered 2011", 200_400),
+            track("SONG (feat. Someone)", 199_000),
+        ]);
+
+        assert_eq!(names(&tracks), ["Song"]);
+    }
+
+    #[test]
+    fn keeps_versions_of_different_length() {
+        let tracks = dedupe_tracks(vec![track("Song", 200_000), track("Song", 260_000)]);
+
+        assert_eq!(tracks.len(), 2);
+    }
+
+    #[test]
+    fn compares_punctuation_only_names_as_written() {
+        let tracks = dedupe_tracks(vec![
+            track("...", 60_000),
+            track("!!!", 60_000),
+            track("...", 60_500),
+        ]);
+
+        assert_eq!(names(&tracks), ["...", "!!!"]);
+    }
+}
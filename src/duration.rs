@@ -0,0 +1,290 @@
+use crate::{MelodyError, MelodyResult};
+
+const SECOND_MS: u64 = 1_000;
+const MINUTE_MS: u64 = 60 * SECOND_MS;
+const HOUR_MS: u64 = 60 * MINUTE_MS;
+const DAY_MS: u64 = 24 * HOUR_MS;
+const WEEK_MS: u64 = 7 * DAY_MS;
+
+// Designators allowed before and after the T, in the order they must appear
+const DATE_UNITS: &[(char, u64)] = &[('W', WEEK_MS), ('D', DAY_MS)];
+const TIME_UNITS: &[(char, u64)] = &[('H', HOUR_MS), ('M', MINUTE_MS), ('S', SECOND_MS)];
+
+// Strict ISO 8601 duration ("PT4M13S", "P1DT2H", "PT1.5S") to milliseconds.
+// Years and months have no fixed length, so they are rejected rather than guessed.
+// Only seconds may be fractional, rounded to the nearest millisecond.
+pub fn parse_iso_duration(duration: &str) -> MelodyResult<u64> {
+    let invalid = |reason: &str| {
+        MelodyError::Decode(format!("invalid ISO 8601 duration '{}': {}", duration, reason))
+    };
+
+    let rest = duration
+        .strip_prefix('P')
+        .ok_or_else(|| invalid("must start with P"))?;
+    let (date_part, time_part) = match rest.split_once('T') {
+        Some((date, time)) => (date, Some(time)),
+        None => (rest, None),
+    };
+
+    if date_part.is_empty() && time_part.is_none() {
+        return Err(invalid("has no components"));
+    }
+    if time_part == Some("") {
+        return Err(invalid("has a T but no time components"));
+    }
+
+    let date_ms = sum_components(date_part, DATE_UNITS, &invalid)?;
+    let time_ms = sum_components(time_part.unwrap_or(""), TIME_UNITS, &invalid)?;
+    date_ms
+        .checked_add(time_ms)
+        .ok_or_else(|| invalid("is too long"))
+}
+
+fn sum_components(
+    mut part: &str,
+    units: &[(char, u64)],
+    invalid: &impl Fn(&str) -> MelodyError,
+) -> MelodyResult<u64> {
+    let mut total: u64 = 0;
+    // Index of the first unit still allowed - enforces order and forbids repeats
+    let mut next_unit = 0;
+
+    while !part.is_empty() {
+        let number_len = part
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+            .unwrap_or(part.len());
+        let number = &part[..number_len];
+        let designator = part[number_len..]
+            .chars()
+            .next()
+            .ok_or_else(|| invalid("ends with a number without a unit"))?;
+        part = &part[number_len + designator.len_utf8()..];
+
+        if number.is_empty() {
+            return Err(invalid("has a unit without a number"));
+        }
+        if units == DATE_UNITS && (designator == 'Y' || designator == 'M') {
+            return Err(invalid("uses years or months, which have no fixed length"));
+        }
+
+        let offset = units[next_unit..]
+            .iter()
+            .position(|(unit, _)| *unit == designator)
+            .ok_or_else(|| {
+                if units.iter().any(|(unit, _)| *unit == designator) {
+                    invalid("has components out of order or repeated")
+                } else {
+                    invalid("has an unknown or misplaced unit")
+                }
+            })?;
+        let (unit, unit_ms) = units[next_unit + offset];
+        next_unit += offset + 1;
+
+        let component_ms = if number.contains(['.', ',']) {
+            if unit != 'S' || !part.is_empty() {
+                return Err(invalid("only the seconds may be fractional"));
+            }
+            parse_fractional_seconds(number).ok_or_else(|| invalid("has a malformed fraction"))?
+        } else {
+            number
+                .parse::<u64>()
+                .ok()
+                .and_then(|value| value.checked_mul(unit_ms))
+                .ok_or_else(|| invalid("is too long"))?
+        };
+
+        total = total
+            .checked_add(component_ms)
+            .ok_or_else(|| invalid("is too long"))?;
+    }
+
+    Ok(total)
+}
+
+// "1.5" / "1,5" seconds -> 1500 ms, rounded half up on the fourth decimal
+fn parse_fractional_seconds(number: &str) -> Option<u64> {
+    let (whole, fraction) = number.split_once(['.', ','])?;
+    if whole.is_empty() || fraction.is_empty() || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut digits = fraction.bytes().map(|b| u64::from(b - b'0'));
+    let mut millis = 0;
+    for _ in 0..3 {
+        millis = millis * 10 + digits.next().unwrap_or(0);
+    }
+    if digits.next().is_some_and(|digit| digit >= 5) {
+        millis += 1;
+    }
+
+    whole
+        .parse::<u64>()
+        .ok()?
+        .checked_mul(SECOND_MS)?
+        .checked_add(millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(duration: &str) -> u64 {
+        parse_iso_duration(duration)
+            .unwrap_or_else(|e| panic!("{} should parse but failed: {}", duration, e))
+    }
+
+    fn rejects(duration: &str) {
+        assert!(
+            parse_iso_duration(duration).is_err(),
+            "{} should be rejected",
+            duration
+        );
+    }
+
+    #[test]
+    fn parses_single_time_components() {
+        assert_eq!(ms("PT0S"), 0);
+        assert_eq!(ms("PT1S"), 1_000);
+        assert_eq!(ms("PT59S"), 59_000);
+        assert_eq!(ms("PT1M"), 60_000);
+        assert_eq!(ms("PT1H"), 3_600_000);
+    }
+
+    #[test]
+    fn parses_typical_youtube_durations() {
+        assert_eq!(ms("PT4M13S"), 253_000);
+        assert_eq!(ms("PT3M"), 180_000);
+        assert_eq!(ms("PT1H2M3S"), 3_723_000);
+        assert_eq!(ms("PT1H3S"), 3_603_000);
+        assert_eq!(ms("PT10H"), 36_000_000);
+    }
+
+    #[test]
+    fn parses_days_and_weeks() {
+        assert_eq!(ms("P1D"), 86_400_000);
+        assert_eq!(ms("P1DT2H"), 93_600_000);
+        assert_eq!(ms("P1DT2H3M4S"), 93_784_000);
+        assert_eq!(ms("P2W"), 1_209_600_000);
+        assert_eq!(ms("P1W1D"), 691_200_000);
+        // YouTube's zero duration for live streams
+        assert_eq!(ms("P0D"), 0);
+    }
+
+    #[test]
+    fn does_not_require_normalized_values() {
+        assert_eq!(ms("PT90S"), 90_000);
+        assert_eq!(ms("PT90M"), 5_400_000);
+        assert_eq!(ms("PT36H"), 129_600_000);
+        assert_eq!(ms("PT007S"), 7_000);
+    }
+
+    #[test]
+    fn parses_fractional_seconds() {
+        assert_eq!(ms("PT1.5S"), 1_500);
+        assert_eq!(ms("PT1,5S"), 1_500);
+        assert_eq!(ms("PT0.001S"), 1);
+        assert_eq!(ms("PT2M3.25S"), 123_250);
+        assert_eq!(ms("PT1.123S"), 1_123);
+    }
+
+    #[test]
+    fn rounds_fractions_to_the_nearest_millisecond() {
+        assert_eq!(ms("PT1.0004S"), 1_000);
+        assert_eq!(ms("PT1.0005S"), 1_001);
+        assert_eq!(ms("PT59.9995S"), 60_000);
+        assert_eq!(ms("PT0.12345678S"), 123);
+    }
+
+    #[test]
+    fn does_not_overflow_u32() {
+        // ~49.7 days is u32::MAX milliseconds
+        assert_eq!(ms("P50D"), 4_320_000_000);
+        assert_eq!(ms("PT4294967296S"), 4_294_967_296_000);
+    }
+
+    #[test]
+    fn rejects_overflowing_u64() {
+        // Too big for u64 before conversion
+        rejects("PT18446744073709551616S");
+        // Fits as seconds, not as milliseconds
+        rejects("PT18446744073709552S");
+        rejects("P30500000000000W");
+        rejects("P213503982335D");
+        // Date and time parts fit on their own, not together
+        rejects("P213503982334DT24H");
+    }
+
+    #[test]
+    fn rejects_missing_components() {
+        rejects("");
+        rejects("P");
+        rejects("PT");
+        rejects("P1DT");
+        rejects("T1S");
+    }
+
+    #[test]
+    fn rejects_missing_prefix_or_wrong_case() {
+        rejects("1S");
+        rejects("4M13S");
+        rejects("pt1s");
+        rejects("Pt1S");
+        rejects(" PT1S");
+        rejects("PT1S ");
+    }
+
+    #[test]
+    fn rejects_years_and_months() {
+        rejects("P1Y");
+        rejects("P1M");
+        rejects("P1Y2M3DT4H");
+    }
+
+    #[test]
+    fn rejects_misplaced_units() {
+        rejects("P1H");
+        rejects("P1S");
+        rejects("PT1D");
+        rejects("PT1W");
+        rejects("PT1X");
+        rejects("P1DT1D");
+    }
+
+    #[test]
+    fn rejects_out_of_order_or_repeated_components() {
+        rejects("PT1S2M");
+        rejects("PT1M1H");
+        rejects("PT1M1M");
+        rejects("P1D1W");
+        rejects("P1D1D");
+    }
+
+    #[test]
+    fn rejects_malformed_numbers() {
+        rejects("PTS");
+        rejects("PT-1S");
+        rejects("PT+1S");
+        rejects("PT1");
+        rejects("PT1 S");
+        rejects("PT1.S");
+        rejects("PT.5S");
+        rejects("PT1.2.3S");
+        rejects("PT1.5e3S");
+        rejects("PT١S");
+    }
+
+    #[test]
+    fn rejects_fractions_outside_the_seconds() {
+        rejects("PT1.5M");
+        rejects("PT1.5H");
+        rejects("P1.5D");
+        rejects("PT1.5S1S");
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        rejects("PT1S1");
+        rejects("PT1SX");
+        rejects("PT4M13S.");
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{
+    lru_cache_stats, update_analytics, ApiResponse, CollectionOutcome, CollectionResponse,
+    ConvertedItem, ItemStatus, KeyHealth, LruCacheStats, MatchOptions, MelodyError, MelodyResult,
+    SongMatch, YouTubeKeyPool, YOUTUBE_KEY_POOL,
+};
+use serde::Serialize;
+use std::{borrow::Cow, future::Future};
+use vercel_runtime::{Body, Error, Request, Response, StatusCode};
+
+// How long browsers and the CDN may keep a complete conversion - 10 minutes
+const CACHE_CONTROL_SUCCESS: &str = "public, max-age=600";
+const CACHE_CONTROL_NONE: &str = "no-cache";
+
+// Everything the conversion endpoints read from a request
+#[derive(Debug, Clone, Default)]
+pub struct ConversionParams {
+    // Spotify ID, link, URI or short link
+    pub input: Option<String>,
+    // `?youtubeAPIKEY=` or the X-YouTube-API-Key header
+    pub youtube_api_key: Option<String>,
+    pub give_length: bool,
+    pub partial: bool,
+    pub market: Option<String>,
+    // `?health=yes` - report this instance's key health next to the result
+    pub health: bool,
+    pub options: MatchOptions,
+}
+
+// State of the instance that served the request. Every endpoint is its own function with its
+// own key pool, so only the instance that made the YouTube calls knows how its keys are doing.
+// The same goes for the in-memory caches.
+#[derive(Debug, Serialize)]
+pub struct InstanceHealth {
+    pub keys: Vec<KeyHealth>,
+    pub caches: LruCacheStats,
+}
+
+impl InstanceHealth {
+    pub fn of(keys: &YouTubeKeyPool) -> Self {
+        Self {
+            keys: keys.health(),
+            caches: lru_cache_stats(),
+        }
+    }
+}
+
+// A response body plus the serving instance's health, when there is any to report
+#[derive(Debug, Serialize)]
+pub struct WithHealth<T> {
+    #[serde(flatten)]
+    pub data: T,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<InstanceHealth>,
+}
+
+// Error bodies carry nothing else to flatten the health into
+#[derive(Serialize)]
+struct HealthOnly {
+    health: InstanceHealth,
+}
+
+// urlencoding passes a stray '%' through unchanged and only fails on invalid UTF-8,
+// so check that every '%' starts a two-digit hex escape first
+fn decode_param(value: &str) -> Option<Cow<'_, str>> {
+    let bytes = value.as_bytes();
+    let valid_escapes = bytes
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte == b'%')
+        .all(|(i, _)| {
+            bytes
+                .get(i + 1..i + 3)
+                .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+        });
+
+    if valid_escapes {
+        urlencoding::decode(value).ok()
+    } else {
+        None
+    }
+}
+
+// Errors only the state of the keys explains
+fn is_key_error(code: &str) -> bool {
+    matches!(code, "quota_exhausted" | "auth_failed")
+}
+
+impl ConversionParams {
+    // A value that is not valid %-encoding is a 400, never a panic
+    pub fn from_request(req: &Request) -> MelodyResult<Self> {
+        let mut params = Self::default();
+
+        for param in req.uri().query().unwrap_or("").split('&') {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = decode_param(value).ok_or_else(|| {
+                MelodyError::InvalidInput(format!("Parameter '{}' is not valid URL encoding", key))
+            })?;
+            let value = value.as_ref();
+
+            match key {
+                "query" if value != "null" && !value.is_empty() => {
+                    params.input = Some(value.to_string());
+                }
+                "youtubeAPIKEY" if value != "default" && !value.is_empty() => {
+                    params.youtube_api_key = Some(value.to_string());
+                }
+                "give_length" => params.give_length = value == "yes",
+                "partial" => params.partial = value == "yes",
+                "market" if !value.is_empty() => params.market = Some(value.to_string()),
+                "health" => params.health = value == "yes" || value == "true",
+                "candidates" => params.options = params.options.with_candidates(value)?,
+                "explain" => params.options = params.options.with_explain(value),
+                "min_confidence" => params.options = params.options.with_min_confidence(value)?,
+                "region" => params.options = params.options.with_region(value),
+                "prefer" => params.options = params.options.with_prefer(value)?,
+                _ => {}
+            }
+        }
+
+        if params.youtube_api_key.is_none() {
+            params.youtube_api_key = req
+                .headers()
+                .get("X-YouTube-API-Key")
+                .and_then(|h| h.to_str().ok())
+                .filter(|s| !s.is_empty() && *s != "default")
+                .map(|s| s.to_string());
+        }
+
+        Ok(params)
+    }
+
+    pub fn require_input(&self, missing_message: &str) -> MelodyResult<&str> {
+        self.input
+            .as_deref()
+            .ok_or_else(|| MelodyError::InvalidInput(missing_message.to_string()))
+    }
+
+    // Shared key pool, with the user-provided key tried first
+    pub fn key_pool(&self) -> YouTubeKeyPool {
+        match &self.youtube_api_key {
+            Some(key) => YOUTUBE_KEY_POOL.with_user_key(key.clone()),
+            None => YOUTUBE_KEY_POOL.clone(),
+        }
+    }
+
+    // Reported when asked for, and always next to errors caused by the keys
+    pub fn health(
+        &self,
+        keys: &YouTubeKeyPool,
+        error_code: Option<&str>,
+    ) -> Option<InstanceHealth> {
+        (self.health || error_code.is_some_and(is_key_error)).then(|| InstanceHealth::of(keys))
+    }
+
+    pub fn with_health<T>(&self, keys: &YouTubeKeyPool, data: T) -> WithHealth<T> {
+        WithHealth {
+            data,
+            health: self.health(keys, None),
+        }
+    }
+}
+
+pub fn json_response<T: Serialize>(
+    status: StatusCode,
+    cache_control: &str,
+    body: &T,
+) -> Result<Response<Body>, Error> {
+    Ok(Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .header("Cache-Control", cache_control)
+        .header("Access-Control-Allow-Origin", "*") // CORS support
+        .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+        .header(
+            "Access-Control-Allow-Headers",
+            "Content-Type, X-YouTube-API-Key",
+        )
+        .header("Vary", "Accept-Encoding") // Compression support
+        .body(serde_json::to_string(body)?.into())?)
+}
+
+// Typed errors carry their own status code and message
+pub fn error_response(error: &MelodyError) -> Result<Response<Body>, Error> {
+    let status =
+        StatusCode::from_u16(error.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    json_response(
+        status,
+        CACHE_CONTROL_NONE,
+        &ApiResponse::<()>::from_error(error),
+    )
+}
+
+// Like error_response, for failures after the key pool was used
+pub fn conversion_error_response(
+    error: &MelodyError,
+    params: &ConversionParams,
+    keys: &YouTubeKeyPool,
+) -> Result<Response<Body>, Error> {
+    match params.health(keys, Some(error.code())) {
+        Some(health) => {
+            let status = StatusCode::from_u16(error.status_code())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let body = ApiResponse::<()>::from_error(error).with_data(HealthOnly { health });
+            json_response(status, CACHE_CONTROL_NONE, &body)
+        }
+        None => error_response(error),
+    }
+}
+
+pub fn success_response<T: Serialize>(data: T) -> Result<Response<Body>, Error> {
+    json_response(
+        StatusCode::OK,
+        CACHE_CONTROL_SUCCESS,
+        &ApiResponse::success(data),
+    )
+}
+
+// A matched song, or a no_match error that keeps the candidates and search query.
+// `wrap` lets an endpoint add its own fields around the song.
+pub fn song_response<T: Serialize>(
+    song: SongMatch,
+    params: &ConversionParams,
+    keys: &YouTubeKeyPool,
+    wrap: impl FnOnce(SongMatch) -> T,
+) -> Result<Response<Body>, Error> {
+    match song.no_match_error() {
+        None => success_response(params.with_health(keys, wrap(song))),
+        Some(error) => {
+            let status = StatusCode::from_u16(error.status_code())
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            let body = ApiResponse::<()>::from_error(&error).with_data(WithHealth {
+                data: wrap(song),
+                health: params.health(keys, Some(error.code())),
+            });
+            json_response(status, CACHE_CONTROL_NONE, &body)
+        }
+    }
+}
+
+// 207 tells the client some items carry an error and can be retried on their own.
+// `wrap` lets an endpoint add its own fields around the collection.
+pub fn collection_response<T: Serialize>(
+    collection: CollectionResponse,
+    params: &ConversionParams,
+    keys: &YouTubeKeyPool,
+    wrap: impl FnOnce(CollectionResponse) -> T,
+) -> Result<Response<Body>, Error> {
+    match collection.outcome(params.partial) {
+        CollectionOutcome::Failed(item_error) => {
+            let status = StatusCode::from_u16(item_error.status_code)
+                .unwrap_or(StatusCode::TOO_MANY_REQUESTS);
+            let body = ApiResponse::<()>::from_item_error(item_error);
+            match params.health(keys, Some(item_error.code)) {
+                Some(health) => json_response(
+                    status,
+                    CACHE_CONTROL_NONE,
+                    &body.with_data(HealthOnly { health }),
+                ),
+                None => json_response(status, CACHE_CONTROL_NONE, &body),
+            }
+        }
+        CollectionOutcome::Partial => json_response(
+            StatusCode::MULTI_STATUS,
+            CACHE_CONTROL_NONE,
+            &ApiResponse::partial(params.with_health(keys, wrap(collection))),
+        ),
+        CollectionOutcome::Complete => success_response(params.with_health(keys, wrap(collection))),
+    }
+}
+
+// Analytics AFTER the response is built - COMPLETELY ASYNC
+pub fn record_conversion(songs: i32, collections: i32) {
+    tokio::spawn(async move {
+        let _ = update_analytics(songs, collections).await;
+    });
+}
+
+// Whole handler of an endpoint that converts one kind of Spotify collection.
+// `convert` gets the Spotify input, the parsed request and the key pool,
+// and returns one item per track.
+pub async fn collection_handler<F, Fut>(
+    req: Request,
+    missing_input_message: &str,
+    convert: F,
+) -> Result<Response<Body>, Error>
+where
+    F: FnOnce(String, ConversionParams, YouTubeKeyPool) -> Fut,
+    Fut: Future<Output = MelodyResult<Vec<ConvertedItem>>>,
+{
+    let params = match ConversionParams::from_request(&req) {
+        Ok(params) => params,
+        Err(e) => return error_response(&e),
+    };
+    let input = match params.require_input(missing_input_message) {
+        Ok(input) => input.to_string(),
+        Err(e) => return error_response(&e),
+    };
+
+    let keys = params.key_pool();
+
+    match convert(input, params.clone(), keys.clone()).await {
+        Ok(items) => {
+            let collection = CollectionResponse::new(items, params.give_length);
+            let songs = collection.count(ItemStatus::Matched) as i32;
+            let response =
+                collection_response(collection, &params, &keys, |collection| collection)?;
+            if response.status().is_success() {
+                record_conversion(songs, 1);
+            }
+            Ok(response)
+        }
+        Err(e) => conversion_error_response(&e, &params, &keys),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_valid_escapes() {
+        assert_eq!(decode_param("a%20b").as_deref(), Some("a b"));
+        assert_eq!(decode_param("caf%C3%A9").as_deref(), Some("café"));
+        assert_eq!(decode_param("plain").as_deref(), Some("plain"));
+    }
+
+    #[test]
+    fn rejects_stray_percent_signs() {
+        assert_eq!(decode_param("%"), None);
+        assert_eq!(decode_param("%zz"), None);
+        assert_eq!(decode_param("abc%2"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert_eq!(decode_param("%FF"), None);
+    }
+}
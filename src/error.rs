@@ -0,0 +1,149 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+// Every failure the library can hand back to a handler.
+// Handlers never inspect error strings - they call status_code() / code() / user_message().
+#[derive(Debug, Error)]
+pub enum MelodyError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Empty(String),
+
+    #[error("authentication failed: {0}")]
+    Auth(String),
+
+    #[error("{0} API quota exhausted")]
+    QuotaExhausted(String),
+
+    #[error("upstream request timed out")]
+    UpstreamTimeout,
+
+    #[error("failed to decode upstream response: {0}")]
+    Decode(String),
+
+    #[error("upstream request failed: {0}")]
+    Upstream(String),
+
+    #[error("server misconfigured: {0}")]
+    Config(String),
+
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    // Best candidate's confidence, below the configured threshold
+    #[error("no candidate reached the match threshold (best confidence {0:.2})")]
+    NoMatch(f64),
+}
+
+pub type MelodyResult<T> = std::result::Result<T, MelodyError>;
+
+impl MelodyError {
+    // THE single mapping from error to HTTP status
+    pub fn status_code(&self) -> u16 {
+        match self {
+            MelodyError::NotFound(_) | MelodyError::Empty(_) | MelodyError::NoMatch(_) => 404,
+            MelodyError::Auth(_) => 401,
+            MelodyError::QuotaExhausted(_) => 429,
+            MelodyError::UpstreamTimeout => 504,
+            MelodyError::Decode(_) | MelodyError::Upstream(_) => 502,
+            MelodyError::Config(_) => 500,
+            MelodyError::InvalidInput(_) => 400,
+        }
+    }
+
+    // Stable machine-readable code returned next to the message
+    pub fn code(&self) -> &'static str {
+        match self {
+            MelodyError::NotFound(_) => "not_found",
+            MelodyError::Empty(_) => "empty",
+            MelodyError::Auth(_) => "auth_failed",
+            MelodyError::QuotaExhausted(_) => "quota_exhausted",
+            MelodyError::UpstreamTimeout => "upstream_timeout",
+            MelodyError::Decode(_) => "decode_error",
+            MelodyError::Upstream(_) => "upstream_error",
+            MelodyError::Config(_) => "config_error",
+            MelodyError::InvalidInput(_) => "invalid_input",
+            MelodyError::NoMatch(_) => "no_match",
+        }
+    }
+
+    // Message safe to show to API users
+    pub fn user_message(&self) -> String {
+        match self {
+            MelodyError::NotFound(what) => format!(
+                "{} not found. Please check if it exists and is public.",
+                what
+            ),
+            MelodyError::Empty(message) | MelodyError::InvalidInput(message) => message.clone(),
+            MelodyError::Auth(_) => "Failed to authenticate with an upstream API.".to_string(),
+            MelodyError::QuotaExhausted(service) => format!(
+                "API Limit Exceeded for all {} API Keys. Please try again later or provide your own YouTube API Key.",
+                service
+            ),
+            MelodyError::UpstreamTimeout => "Request timeout. Please try again.".to_string(),
+            MelodyError::Decode(_) | MelodyError::Upstream(_) => {
+                "An upstream API returned an unexpected response. Please try again later.".to_string()
+            }
+            MelodyError::Config(_) => {
+                "The server is missing required configuration. Please contact the maintainer.".to_string()
+            }
+            MelodyError::NoMatch(_) => {
+                "No YouTube video matched this track closely enough.".to_string()
+            }
+        }
+    }
+
+    // Map a failed Spotify Web API status to the right variant
+    pub fn from_spotify_status(status: StatusCode, resource: &str) -> Self {
+        match status {
+            // Spotify answers malformed IDs with 400 "invalid id"
+            StatusCode::NOT_FOUND | StatusCode::BAD_REQUEST => {
+                MelodyError::NotFound(resource.to_string())
+            }
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                MelodyError::Auth(format!("Spotify returned {}", status))
+            }
+            StatusCode::TOO_MANY_REQUESTS => MelodyError::QuotaExhausted("Spotify".to_string()),
+            _ => MelodyError::Upstream(format!("Spotify returned {}", status)),
+        }
+    }
+
+    // Pick the error that best explains why every API key failed.
+    // Quota beats everything else since it is the one the user can act on.
+    pub fn most_relevant(errors: Vec<MelodyError>) -> Self {
+        fn rank(error: &MelodyError) -> u8 {
+            match error {
+                MelodyError::QuotaExhausted(_) => 0,
+                MelodyError::Auth(_) => 1,
+                MelodyError::NotFound(_) | MelodyError::Empty(_) | MelodyError::NoMatch(_) => 2,
+                MelodyError::UpstreamTimeout => 3,
+                _ => 4,
+            }
+        }
+
+        errors
+            .into_iter()
+            .min_by_key(rank)
+            .unwrap_or_else(|| MelodyError::Config("no YouTube API keys configured".to_string()))
+    }
+}
+
+impl From<reqwest::Error> for MelodyError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            MelodyError::UpstreamTimeout
+        } else if e.is_decode() {
+            MelodyError::Decode(e.to_string())
+        } else {
+            MelodyError::Upstream(e.to_string())
+        }
+    }
+}
+
+impl From<serde_json::Error> for MelodyError {
+    fn from(e: serde_json::Error) -> Self {
+        MelodyError::Decode(e.to_string())
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{MelodyError, MelodyResult};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use reqwest::Client;
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpotifyResourceKind {
+    Track,
+    Album,
+    Playlist,
+    Artist,
+}
+
+impl SpotifyResourceKind {
+    fn from_segment(segment: &str) -> Option<Self> {
+        match segment {
+            "track" => Some(Self::Track),
+            "album" => Some(Self::Album),
+            "playlist" => Some(Self::Playlist),
+            "artist" => Some(Self::Artist),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Track => "track",
+            Self::Album => "album",
+            Self::Playlist => "playlist",
+            Self::Artist => "artist",
+        }
+    }
+}
+
+// What the user pointed at. A bare ID carries no kind - the endpoint decides what it is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpotifyResource {
+    pub kind: Option<SpotifyResourceKind>,
+    pub id: String,
+}
+
+impl SpotifyResource {
+    // The ID if this resource can be used where `expected` is needed
+    pub fn id_for(self, expected: SpotifyResourceKind) -> MelodyResult<String> {
+        match self.kind {
+            Some(kind) if kind != expected => Err(MelodyError::InvalidInput(format!(
+                "Expected a Spotify {} but got a {} link",
+                expected.as_str(),
+                kind.as_str()
+            ))),
+            _ => Ok(self.id),
+        }
+    }
+}
+
+// Short links redirect through a web page - they need a normal HTTP/1.1 client that follows redirects
+static SHORT_LINK_CLIENT: Lazy<Client> = Lazy::new(|| {
+    Client::builder()
+        .redirect(reqwest::redirect::Policy::limited(5))
+        .timeout(Duration::from_secs(5))
+        .use_rustls_tls()
+        .build()
+        .expect("Failed to create short link client")
+});
+
+static SPOTIFY_URL: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?:https?://)?(?:open|play)\.spotify\.com/(?:intl-[a-zA-Z-]+/)?(?:embed/)?(?:user/[^/]+/)?(track|album|playlist|artist)/([A-Za-z0-9]+)",
+    )
+    .unwrap()
+});
+
+static SPOTIFY_URI: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^spotify:(?:user:[^:]+:)?(track|album|playlist|artist):([A-Za-z0-9]+)$").unwrap()
+});
+
+static SHORT_LINK: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:https?://)?(?:spotify\.link|spoti\.fi)/[A-Za-z0-9_-]+").unwrap());
+
+static BARE_ID: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[A-Za-z0-9]+$").unwrap());
+
+pub fn is_short_link(input: &str) -> bool {
+    SHORT_LINK.is_match(input.trim())
+}
+
+// Normalize a bare ID, an open.spotify.com URL (with or without ?si= and intl- prefixes)
+// or a spotify: URI. Short links need a network round trip - use resolve_spotify_input.
+pub fn parse_spotify_input(input: &str) -> MelodyResult<SpotifyResource> {
+    let input = input.trim();
+
+    let caps = SPOTIFY_URL
+        .captures(input)
+        .or_else(|| SPOTIFY_URI.captures(input));
+    if let Some(caps) = caps {
+        return Ok(SpotifyResource {
+            kind: SpotifyResourceKind::from_segment(&caps[1]),
+            id: caps[2].to_string(),
+        });
+    }
+
+    if BARE_ID.is_match(input) {
+        return Ok(SpotifyResource {
+            kind: None,
+            id: input.to_string(),
+        });
+    }
+
+    Err(MelodyError::InvalidInput(format!(
+        "Could not read a Spotify ID, link or URI from '{}'",
+        input
+    )))
+}
+
+// Spotify markets are ISO 3166-1 alpha-2 country codes
+pub fn parse_market(input: &str) -> MelodyResult<String> {
+    let market = input.trim();
+    if market.len() == 2 && market.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(market.to_ascii_uppercase())
+    } else {
+        Err(MelodyError::InvalidInput(format!(
+            "'{}' is not a valid market - use a two-letter country code like US",
+            market
+        )))
+    }
+}
+
+// What every endpoint accepts as a Spotify input: a bare ID, an open.spotify.com link, a
+// spotify: URI, or a spotify.link / spoti.fi short link, which is followed
+pub async fn resolve_spotify_input(input: &str) -> MelodyResult<SpotifyResource> {
+    let input = input.trim();
+    if !is_short_link(input) {
+        return parse_spotify_input(input);
+    }
+
+    let url = if input.starts_with("http") {
+        input.to_string()
+    } else {
+        format!("https://{}", input)
+    };
+    let response = SHORT_LINK_CLIENT.get(&url).send().await?;
+
+    // Usually the redirect lands on open.spotify.com directly
+    if let Ok(resource) = parse_spotify_input(response.url().as_str()) {
+        return Ok(resource);
+    }
+
+    // Otherwise the landing page links to it
+    static EMBEDDED_URL: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"https://open\.spotify\.com/(?:track|album|playlist|artist)/[A-Za-z0-9]+").unwrap()
+    });
+    let body = response.text().await?;
+    EMBEDDED_URL
+        .find(&body)
+        .and_then(|found| parse_spotify_input(found.as_str()).ok())
+        .ok_or_else(|| MelodyError::InvalidInput(format!("Could not resolve short link '{}'", input)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "4uLU6hMCjMI75M1A2tKUQC";
+
+    fn parsed(input: &str) -> (Option<SpotifyResourceKind>, String) {
+        let resource = parse_spotify_input(input)
+            .unwrap_or_else(|e| panic!("{} should parse but failed: {}", input, e));
+        (resource.kind, resource.id)
+    }
+
+    fn track(id: &str) -> (Option<SpotifyResourceKind>, String) {
+        (Some(SpotifyResourceKind::Track), id.to_string())
+    }
+
+    fn rejects(input: &str) {
+        assert!(
+            parse_spotify_input(input).is_err(),
+            "{} should be rejected",
+            input
+        );
+    }
+
+    #[test]
+    fn parses_bare_ids() {
+        assert_eq!(parsed(ID), (None, ID.to_string()));
+        assert_eq!(parsed(&format!("  {}\n", ID)), (None, ID.to_string()));
+    }
+
+    #[test]
+    fn parses_open_spotify_links() {
+        let expected = track(ID);
+        assert_eq!(parsed(&format!("https://open.spotify.com/track/{}", ID)), expected);
+        assert_eq!(parsed(&format!("http://open.spotify.com/track/{}", ID)), expected);
+        assert_eq!(parsed(&format!("open.spotify.com/track/{}", ID)), expected);
+        assert_eq!(parsed(&format!("https://play.spotify.com/track/{}", ID)), expected);
+        assert_eq!(parsed(&format!(" https://open.spotify.com/track/{} ", ID)), expected);
+    }
+
+    #[test]
+    fn ignores_query_strings() {
+        assert_eq!(
+            parsed(&format!("https://open.spotify.com/track/{}?si=abc123XYZ", ID)),
+            track(ID)
+        );
+        assert_eq!(
+            parsed(&format!("https://open.spotify.com/track/{}?si=abc&context=x", ID)),
+            track(ID)
+        );
+    }
+
+    #[test]
+    fn parses_intl_embed_and_user_links() {
+        assert_eq!(
+            parsed(&format!("https://open.spotify.com/intl-de/track/{}", ID)),
+            track(ID)
+        );
+        assert_eq!(
+            parsed(&format!("https://open.spotify.com/intl-pt-BR/track/{}?si=x", ID)),
+            track(ID)
+        );
+        assert_eq!(
+            parsed(&format!("https://open.spotify.com/embed/track/{}", ID)),
+            track(ID)
+        );
+        assert_eq!(
+            parsed("https://open.spotify.com/user/spotify/playlist/37i9dQZF1DXcBWIGoYBM5M"),
+            (
+                Some(SpotifyResourceKind::Playlist),
+                "37i9dQZF1DXcBWIGoYBM5M".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn parses_every_resource_kind() {
+        let kind = |input: &str| parsed(input).0;
+        assert_eq!(
+            kind("https://open.spotify.com/album/1A2GTWGtFfWp7KSQTwWOyo"),
+            Some(SpotifyResourceKind::Album)
+        );
+        assert_eq!(
+            kind("https://open.spotify.com/playlist/37i9dQZF1DXcBWIGoYBM5M"),
+            Some(SpotifyResourceKind::Playlist)
+        );
+        assert_eq!(
+            kind("https://open.spotify.com/artist/0OdUWJ0sBjDrqHygGUXeCF"),
+            Some(SpotifyResourceKind::Artist)
+        );
+    }
+
+    #[test]
+    fn parses_uris() {
+        assert_eq!(parsed(&format!("spotify:track:{}", ID)), track(ID));
+        assert_eq!(
+            parsed("spotify:user:spotify:playlist:37i9dQZF1DXcBWIGoYBM5M"),
+            (
+                Some(SpotifyResourceKind::Playlist),
+                "37i9dQZF1DXcBWIGoYBM5M".to_string()
+            )
+        );
+        rejects(&format!("spotify:track:{}:extra", ID));
+    }
+
+    #[test]
+    fn rejects_episodes_and_shows() {
+        rejects("https://open.spotify.com/episode/512ojhOuo1ktJprKbVcKyQ");
+        rejects("https://open.spotify.com/show/2MAi0BvDc6GTFvKFPXnkCL");
+        rejects("spotify:episode:512ojhOuo1ktJprKbVcKyQ");
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        rejects("");
+        rejects("   ");
+        rejects("https://www.youtube.com/watch?v=dQw4w9WgXcQ");
+        rejects("https://open.spotify.com/");
+        rejects("not an id");
+        rejects("abc-def");
+    }
+
+    #[test]
+    fn recognizes_short_links() {
+        assert!(is_short_link("https://spotify.link/AbC123"));
+        assert!(is_short_link("spoti.fi/3xYz"));
+        assert!(is_short_link("  https://spotify.link/AbC123  "));
+        assert!(!is_short_link(&format!("https://open.spotify.com/track/{}", ID)));
+    }
+
+    #[test]
+    fn rejects_wrong_kind() {
+        let album = parse_spotify_input("https://open.spotify.com/album/1A2GTWGtFfWp7KSQTwWOyo");
+        assert!(album.unwrap().id_for(SpotifyResourceKind::Track).is_err());
+        assert_eq!(
+            parse_spotify_input(ID).unwrap().id_for(SpotifyResourceKind::Track).unwrap(),
+            ID
+        );
+    }
+}
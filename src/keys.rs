@@ -0,0 +1,821 @@
+use crate::{MelodyError, MelodyResult};
+use futures::stream::{FuturesUnordered, StreamExt};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    env, fs,
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// Quota units YouTube charges per call
+pub const SEARCH_QUOTA_COST: u64 = 100;
+pub const VIDEOS_QUOTA_COST: u64 = 1;
+pub const CHANNELS_QUOTA_COST: u64 = 1;
+
+// Default daily allowance of a Google Cloud project, override with YOUTUBE_DAILY_QUOTA
+const DEFAULT_DAILY_QUOTA: u64 = 10_000;
+
+// Circuit breaker - this many transient failures in a row parks a key for the cooldown
+const BREAKER_THRESHOLD: u32 = 3;
+const BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+// How a YouTube call picks its API key. Every strategy still fails over when a key is dead,
+// but only one key is charged quota for a call that succeeds on its first try.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyStrategy {
+    // Try keys in order, moving on only when one fails
+    #[default]
+    Sequential,
+    // Like Sequential, but if a key has not answered after the delay the next one is fired too
+    Hedged(Duration),
+    // Rotate the starting key on every call to spread quota evenly
+    RoundRobin,
+}
+
+const DEFAULT_HEDGE_DELAY: Duration = Duration::from_millis(800);
+
+// Read once per warm instance from YOUTUBE_KEY_STRATEGY
+pub static KEY_STRATEGY: Lazy<KeyStrategy> = Lazy::new(KeyStrategy::from_env);
+
+// Shared across requests on a warm instance so key health survives between invocations
+pub static YOUTUBE_KEY_POOL: Lazy<YouTubeKeyPool> = Lazy::new(YouTubeKeyPool::from_env);
+
+static ROUND_ROBIN_CURSOR: AtomicUsize = AtomicUsize::new(0);
+
+impl KeyStrategy {
+    // YOUTUBE_KEY_STRATEGY = sequential | round_robin | hedged, YOUTUBE_HEDGE_DELAY_MS tunes hedging
+    pub fn from_env() -> Self {
+        let delay = env::var("YOUTUBE_HEDGE_DELAY_MS")
+            .ok()
+            .and_then(|ms| ms.parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_HEDGE_DELAY);
+
+        match env::var("YOUTUBE_KEY_STRATEGY").as_deref() {
+            Ok("hedged") => KeyStrategy::Hedged(delay),
+            Ok("round_robin") => KeyStrategy::RoundRobin,
+            _ => KeyStrategy::Sequential,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+    Healthy,
+    // Transient failures tripped the breaker, the key is retried after the cooldown
+    CircuitOpen,
+    // YouTube said quotaExceeded - parked until the daily reset
+    Exhausted,
+    // YouTube said keyInvalid - never used again on this instance
+    Invalid,
+}
+
+// Snapshot of one key for `health=yes` - never includes the key itself
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyHealth {
+    pub label: String,
+    pub status: KeyStatus,
+    pub quota_used: u64,
+    pub quota_remaining: u64,
+    pub consecutive_failures: u32,
+    // Unix seconds of the next Pacific-midnight quota reset
+    pub quota_resets_at: u64,
+}
+
+#[derive(Debug)]
+struct KeyUsage {
+    spent: u64,
+    resets_at: SystemTime,
+    exhausted: bool,
+    invalid: bool,
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct KeyState {
+    key: String,
+    label: String,
+    priority: i32,
+    daily_quota: u64,
+    usage: Mutex<KeyUsage>,
+}
+
+impl KeyState {
+    fn new(key: String, label: String, priority: i32, daily_quota: u64) -> Self {
+        Self {
+            key,
+            label,
+            priority,
+            daily_quota,
+            usage: Mutex::new(KeyUsage {
+                spent: 0,
+                resets_at: next_pacific_midnight(SystemTime::now()),
+                exhausted: false,
+                invalid: false,
+                consecutive_failures: 0,
+                open_until: None,
+            }),
+        }
+    }
+
+    fn usage(&self) -> std::sync::MutexGuard<'_, KeyUsage> {
+        let mut usage = self.usage.lock().unwrap_or_else(|e| e.into_inner());
+
+        // New quota day - forget yesterday's spend and exhaustion
+        let now = SystemTime::now();
+        if now >= usage.resets_at {
+            usage.spent = 0;
+            usage.exhausted = false;
+            usage.resets_at = next_pacific_midnight(now);
+        }
+
+        usage
+    }
+
+    fn status(&self) -> KeyStatus {
+        let usage = self.usage();
+        if usage.invalid {
+            KeyStatus::Invalid
+        } else if usage.exhausted {
+            KeyStatus::Exhausted
+        } else if usage.open_until.is_some_and(|until| until > Instant::now()) {
+            KeyStatus::CircuitOpen
+        } else {
+            KeyStatus::Healthy
+        }
+    }
+
+    fn remaining(&self) -> u64 {
+        self.daily_quota.saturating_sub(self.usage().spent)
+    }
+
+    fn record<T>(&self, cost: u64, result: &MelodyResult<T>) {
+        let mut usage = self.usage();
+
+        match result {
+            Ok(_) | Err(MelodyError::NotFound(_)) => {
+                usage.spent += cost;
+                usage.consecutive_failures = 0;
+                usage.open_until = None;
+            }
+            Err(MelodyError::QuotaExhausted(_)) => {
+                usage.spent = usage.spent.max(self.daily_quota);
+                usage.exhausted = true;
+            }
+            Err(MelodyError::Auth(_)) => usage.invalid = true,
+            Err(_) => {
+                // The call may still have been charged, so count it
+                usage.spent += cost;
+                usage.consecutive_failures += 1;
+                if usage.consecutive_failures >= BREAKER_THRESHOLD {
+                    usage.open_until = Some(Instant::now() + BREAKER_COOLDOWN);
+                }
+            }
+        }
+    }
+
+    fn health(&self) -> KeyHealth {
+        let status = self.status();
+        let usage = self.usage();
+        KeyHealth {
+            label: self.label.clone(),
+            status,
+            quota_used: usage.spent,
+            quota_remaining: self.daily_quota.saturating_sub(usage.spent),
+            consecutive_failures: usage.consecutive_failures,
+            quota_resets_at: usage
+                .resets_at
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+}
+
+// YouTube resets quotas at midnight Pacific Time. We use PST (UTC-8) all year, which
+// during daylight saving time errs an hour late - a key is never retried too early.
+fn next_pacific_midnight(now: SystemTime) -> SystemTime {
+    const DAY: u64 = 86_400;
+    const PACIFIC_OFFSET: u64 = 8 * 3600;
+
+    let utc = now.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let pacific_day = (utc.saturating_sub(PACIFIC_OFFSET)) / DAY;
+    UNIX_EPOCH + Duration::from_secs((pacific_day + 1) * DAY + PACIFIC_OFFSET)
+}
+
+// One server-side key as configured. Higher priority keys are tried first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YouTubeKeyConfig {
+    pub key: String,
+    pub label: String,
+    pub priority: i32,
+}
+
+// Parse one `[label=]key[:priority]` entry - the same syntax is used in every key source
+fn parse_key_entry(entry: &str, default_label: &str) -> Option<YouTubeKeyConfig> {
+    let entry = entry.trim();
+    let (label, rest) = match entry.split_once('=') {
+        Some((label, rest)) => (label.trim(), rest.trim()),
+        None => (default_label, entry),
+    };
+    let (key, priority) = match rest.rsplit_once(':') {
+        Some((key, priority)) => match priority.trim().parse() {
+            Ok(priority) => (key.trim(), priority),
+            // Not a priority after all - let YouTube judge the whole thing as a key
+            Err(_) => (rest, 0),
+        },
+        None => (rest, 0),
+    };
+
+    // "default" is the placeholder the old config shipped with - never send it to Google
+    if key.is_empty() || key == "default" {
+        return None;
+    }
+
+    Some(YouTubeKeyConfig {
+        key: key.to_string(),
+        label: label.to_string(),
+        priority,
+    })
+}
+
+// Collect every server-side YouTube key from, in this order:
+// - YOUTUBE_API_KEYS: comma-separated entries
+// - YOUTUBE_API_KEY, YOUTUBE_API_KEY2, YOUTUBE_API_KEY_3, ... with no upper limit
+// - YOUTUBE_API_KEYS_FILE: one entry per line, blank lines and # comments ignored
+// Each entry is `[label=]key[:priority]`. A key listed twice is only kept the first time.
+pub fn get_youtube_api_keys() -> MelodyResult<Vec<YouTubeKeyConfig>> {
+    let mut keys = Vec::new();
+
+    if let Ok(list) = env::var("YOUTUBE_API_KEYS") {
+        keys.extend(list_entries(&list));
+    }
+
+    keys.extend(numbered_entries(env::vars()));
+
+    if let Ok(path) = env::var("YOUTUBE_API_KEYS_FILE") {
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            MelodyError::Config(format!("could not read YOUTUBE_API_KEYS_FILE {}: {}", path, e))
+        })?;
+        keys.extend(file_entries(&contents, &path));
+    }
+
+    dedupe_keys(&mut keys);
+
+    if keys.is_empty() {
+        return Err(MelodyError::Config(
+            "no YouTube API keys configured - set YOUTUBE_API_KEYS, YOUTUBE_API_KEY_N or YOUTUBE_API_KEYS_FILE"
+                .to_string(),
+        ));
+    }
+
+    Ok(keys)
+}
+
+// YOUTUBE_API_KEYS - comma-separated, labelled by position unless an entry names itself
+fn list_entries(list: &str) -> Vec<YouTubeKeyConfig> {
+    list.split(',')
+        .enumerate()
+        .filter_map(|(i, entry)| parse_key_entry(entry, &format!("YOUTUBE_API_KEYS[{}]", i)))
+        .collect()
+}
+
+// YOUTUBE_API_KEY, YOUTUBE_API_KEY2, YOUTUBE_API_KEY_3... in number order, the unnumbered one as 1
+fn numbered_entries(vars: impl IntoIterator<Item = (String, String)>) -> Vec<YouTubeKeyConfig> {
+    static NUMBERED_KEY: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^YOUTUBE_API_KEY(?:_?(\d+))?$").unwrap());
+
+    let mut numbered: Vec<(u32, String, String)> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let number = NUMBERED_KEY
+                .captures(&name)?
+                .get(1)
+                .map_or(Some(1), |n| n.as_str().parse().ok())?;
+            Some((number, name, value))
+        })
+        .collect();
+    numbered.sort();
+
+    numbered
+        .iter()
+        .filter_map(|(_, name, value)| parse_key_entry(value, name))
+        .collect()
+}
+
+// YOUTUBE_API_KEYS_FILE - one entry per line, labelled path:line
+fn file_entries(contents: &str, path: &str) -> Vec<YouTubeKeyConfig> {
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .filter_map(|(i, line)| parse_key_entry(line, &format!("{}:{}", path, i + 1)))
+        .collect()
+}
+
+// The first mention of a key wins
+fn dedupe_keys(keys: &mut Vec<YouTubeKeyConfig>) {
+    let mut seen = std::collections::HashSet::new();
+    keys.retain(|config| seen.insert(config.key.clone()));
+}
+
+// Every configured YouTube key together with what we know about its health
+#[derive(Debug, Clone)]
+pub struct YouTubeKeyPool {
+    keys: Vec<Arc<KeyState>>,
+    strategy: KeyStrategy,
+    // Why the server has no keys of its own, reported when a request brings none either
+    config_error: Option<String>,
+}
+
+impl YouTubeKeyPool {
+    pub fn from_env() -> Self {
+        match get_youtube_api_keys() {
+            Ok(configs) => Self::new(configs, *KEY_STRATEGY),
+            Err(e) => Self {
+                keys: Vec::new(),
+                strategy: *KEY_STRATEGY,
+                config_error: Some(match e {
+                    MelodyError::Config(message) => message,
+                    other => other.to_string(),
+                }),
+            },
+        }
+    }
+
+    pub fn new(configs: Vec<YouTubeKeyConfig>, strategy: KeyStrategy) -> Self {
+        let daily_quota = env::var("YOUTUBE_DAILY_QUOTA")
+            .ok()
+            .and_then(|quota| quota.parse().ok())
+            .unwrap_or(DEFAULT_DAILY_QUOTA);
+
+        let keys = configs
+            .into_iter()
+            .map(|config| {
+                let label = format!("{} (…{})", config.label, key_suffix(&config.key));
+                Arc::new(KeyState::new(config.key, label, config.priority, daily_quota))
+            })
+            .collect();
+
+        Self {
+            keys,
+            strategy,
+            config_error: None,
+        }
+    }
+
+    // Pool for one request with the caller's own key tried before any server key
+    pub fn with_user_key(&self, key: String) -> Self {
+        let label = format!("user key (…{})", key_suffix(&key));
+        let mut keys = vec![Arc::new(KeyState::new(key, label, i32::MAX, DEFAULT_DAILY_QUOTA))];
+        keys.extend(self.keys.iter().cloned());
+
+        Self {
+            keys,
+            strategy: self.strategy,
+            config_error: None,
+        }
+    }
+
+    pub fn health(&self) -> Vec<KeyHealth> {
+        self.keys.iter().map(|key| key.health()).collect()
+    }
+
+    // Labels only - safe to show, and true on any instance whatever it has spent
+    pub fn labels(&self) -> Vec<String> {
+        self.keys.iter().map(|key| key.label.clone()).collect()
+    }
+
+    // Why the server has no keys, if it has none
+    pub fn config_error(&self) -> Option<&str> {
+        self.config_error.as_deref()
+    }
+
+    // Usable keys, healthiest first: priority, then the most quota left.
+    // Keys behind an open breaker are kept as a last resort.
+    fn ordered(&self) -> MelodyResult<Vec<Arc<KeyState>>> {
+        let mut healthy = Vec::new();
+        let mut cooling_down = Vec::new();
+        let mut any_exhausted = false;
+
+        for key in &self.keys {
+            match key.status() {
+                KeyStatus::Healthy => healthy.push(key.clone()),
+                KeyStatus::CircuitOpen => cooling_down.push(key.clone()),
+                KeyStatus::Exhausted => any_exhausted = true,
+                KeyStatus::Invalid => {}
+            }
+        }
+
+        healthy.sort_by_cached_key(|key| (Reverse(key.priority), Reverse(key.remaining())));
+        healthy.extend(cooling_down);
+
+        if healthy.is_empty() {
+            return Err(if any_exhausted {
+                MelodyError::QuotaExhausted("YouTube".to_string())
+            } else if self.keys.is_empty() {
+                MelodyError::Config(
+                    self.config_error
+                        .clone()
+                        .unwrap_or_else(|| "no YouTube API keys configured".to_string()),
+                )
+            } else {
+                MelodyError::Auth("every YouTube API key is invalid".to_string())
+            });
+        }
+
+        Ok(healthy)
+    }
+
+    // Run one YouTube call costing `cost` quota units, handing each attempt an API key
+    pub(crate) async fn call<T, F, Fut>(&self, cost: u64, request: F) -> MelodyResult<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = MelodyResult<T>>,
+    {
+        let keys = self.ordered()?;
+        let request = &request;
+        let attempt = |key: Arc<KeyState>| async move {
+            let result = request(key.key.clone()).await;
+            key.record(cost, &result);
+            result
+        };
+
+        match self.strategy {
+            KeyStrategy::Sequential => sequential(&keys, 0, attempt).await,
+            KeyStrategy::RoundRobin => {
+                let start = ROUND_ROBIN_CURSOR.fetch_add(1, Ordering::Relaxed) % keys.len();
+                sequential(&keys, start, attempt).await
+            }
+            KeyStrategy::Hedged(delay) => hedged(&keys, delay, attempt).await,
+        }
+    }
+}
+
+fn key_suffix(key: &str) -> &str {
+    let start = key.char_indices().rev().nth(3).map(|(i, _)| i).unwrap_or(0);
+    &key[start..]
+}
+
+// Errors that say nothing about the key itself - retrying with another key won't help
+fn is_final(error: &MelodyError) -> bool {
+    matches!(
+        error,
+        MelodyError::NotFound(_)
+            | MelodyError::Empty(_)
+            | MelodyError::Config(_)
+            | MelodyError::InvalidInput(_)
+            | MelodyError::NoMatch(_)
+    )
+}
+
+async fn sequential<K, T, F, Fut>(keys: &[K], start: usize, attempt: F) -> MelodyResult<T>
+where
+    K: Clone,
+    F: Fn(K) -> Fut,
+    Fut: Future<Output = MelodyResult<T>>,
+{
+    let mut errors = Vec::new();
+
+    for offset in 0..keys.len() {
+        match attempt(keys[(start + offset) % keys.len()].clone()).await {
+            Ok(value) => return Ok(value),
+            Err(e) if is_final(&e) => return Err(e),
+            Err(e) => errors.push(e), // Try next API key
+        }
+    }
+
+    Err(MelodyError::most_relevant(errors))
+}
+
+async fn hedged<K, T, F, Fut>(keys: &[K], delay: Duration, attempt: F) -> MelodyResult<T>
+where
+    K: Clone,
+    F: Fn(K) -> Fut,
+    Fut: Future<Output = MelodyResult<T>>,
+{
+    let mut in_flight = FuturesUnordered::new();
+    let mut remaining = keys.iter();
+    let mut errors = Vec::new();
+
+    if let Some(key) = remaining.next() {
+        in_flight.push(attempt(key.clone()));
+    }
+
+    while !in_flight.is_empty() {
+        let has_spare_key = remaining.len() > 0;
+
+        tokio::select! {
+            Some(result) = in_flight.next() => match result {
+                Ok(value) => return Ok(value),
+                Err(e) if is_final(&e) => return Err(e),
+                Err(e) => {
+                    errors.push(e);
+                    // A failed key is replaced straight away rather than after the delay
+                    if let Some(key) = remaining.next() {
+                        in_flight.push(attempt(key.clone()));
+                    }
+                }
+            },
+            _ = tokio::time::sleep(delay), if has_spare_key => {
+                if let Some(key) = remaining.next() {
+                    in_flight.push(attempt(key.clone()));
+                }
+            }
+        }
+    }
+
+    Err(MelodyError::most_relevant(errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(key: &str, label: &str, priority: i32) -> YouTubeKeyConfig {
+        YouTubeKeyConfig {
+            key: key.to_string(),
+            label: label.to_string(),
+            priority,
+        }
+    }
+
+    fn keys_of(configs: &[YouTubeKeyConfig]) -> Vec<&str> {
+        configs.iter().map(|config| config.key.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_label_key_and_priority() {
+        assert_eq!(
+            parse_key_entry(" main = AIzaKey1 : 5 ", "fallback"),
+            Some(config("AIzaKey1", "main", 5))
+        );
+        assert_eq!(
+            parse_key_entry("AIzaKey1", "fallback"),
+            Some(config("AIzaKey1", "fallback", 0))
+        );
+        assert_eq!(
+            parse_key_entry("AIzaKey1:-2", "fallback"),
+            Some(config("AIzaKey1", "fallback", -2))
+        );
+    }
+
+    #[test]
+    fn keeps_the_whole_entry_when_the_priority_is_not_a_number() {
+        assert_eq!(
+            parse_key_entry("backup=AIza:Key", "fallback"),
+            Some(config("AIza:Key", "backup", 0))
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_placeholder_keys() {
+        assert_eq!(parse_key_entry("default", "fallback"), None);
+        assert_eq!(parse_key_entry("main=default:3", "fallback"), None);
+        assert_eq!(parse_key_entry("  ", "fallback"), None);
+        assert_eq!(parse_key_entry("main=", "fallback"), None);
+    }
+
+    #[test]
+    fn labels_list_entries_by_position() {
+        assert_eq!(
+            list_entries("AIzaA, named=AIzaB,,default"),
+            vec![
+                config("AIzaA", "YOUTUBE_API_KEYS[0]", 0),
+                config("AIzaB", "named", 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn orders_numbered_variables_by_number() {
+        let vars = [
+            ("YOUTUBE_API_KEY_10", "AIza10"),
+            ("YOUTUBE_API_KEY2", "AIza2"),
+            ("YOUTUBE_API_KEYS_FILE", "/tmp/keys.txt"),
+            ("YOUTUBE_API_KEY", "AIza1"),
+            ("YOUTUBE_API_KEY_3", "AIza3"),
+            ("OTHER_API_KEY", "AIzaOther"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let keys = numbered_entries(vars);
+        assert_eq!(keys_of(&keys), ["AIza1", "AIza2", "AIza3", "AIza10"]);
+        assert_eq!(keys[1].label, "YOUTUBE_API_KEY2");
+    }
+
+    #[test]
+    fn skips_comments_and_blank_lines_in_key_files() {
+        let contents = "# server keys\nAIzaA\n\n  # old = AIzaOld\nbackup=AIzaB:2\n";
+
+        assert_eq!(
+            file_entries(contents, "keys.txt"),
+            vec![
+                config("AIzaA", "keys.txt:2", 0),
+                config("AIzaB", "backup", 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_the_first_mention_of_a_key() {
+        let mut keys = vec![
+            config("AIzaA", "first", 1),
+            config("AIzaB", "other", 0),
+            config("AIzaA", "second", 9),
+        ];
+        dedupe_keys(&mut keys);
+
+        assert_eq!(
+            keys,
+            vec![config("AIzaA", "first", 1), config("AIzaB", "other", 0)]
+        );
+    }
+
+    fn key_state() -> KeyState {
+        KeyState::new("AIzaKey".to_string(), "test".to_string(), 0, 1_000)
+    }
+
+    fn upstream() -> MelodyResult<()> {
+        Err(MelodyError::Upstream("YouTube returned 500".to_string()))
+    }
+
+    #[test]
+    fn quota_errors_exhaust_the_key() {
+        let key = key_state();
+        key.record(
+            SEARCH_QUOTA_COST,
+            &Err::<(), _>(MelodyError::QuotaExhausted("YouTube".into())),
+        );
+
+        assert_eq!(key.status(), KeyStatus::Exhausted);
+        assert_eq!(key.remaining(), 0);
+    }
+
+    #[test]
+    fn auth_errors_invalidate_the_key() {
+        let key = key_state();
+        key.record(
+            SEARCH_QUOTA_COST,
+            &Err::<(), _>(MelodyError::Auth("keyInvalid".into())),
+        );
+
+        assert_eq!(key.status(), KeyStatus::Invalid);
+    }
+
+    #[test]
+    fn breaker_opens_after_three_failures_in_a_row() {
+        let key = key_state();
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+        assert_eq!(key.status(), KeyStatus::Healthy);
+
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+        assert_eq!(key.status(), KeyStatus::CircuitOpen);
+        // Failed calls may still be charged
+        assert_eq!(key.remaining(), 997);
+    }
+
+    #[test]
+    fn success_resets_the_failure_count() {
+        let key = key_state();
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+        key.record(SEARCH_QUOTA_COST, &Ok(()));
+        key.record(VIDEOS_QUOTA_COST, &upstream());
+
+        assert_eq!(key.status(), KeyStatus::Healthy);
+        assert_eq!(key.health().consecutive_failures, 1);
+    }
+
+    #[test]
+    fn quota_resets_at_the_next_pacific_midnight() {
+        // 2024-01-15 00:00 UTC
+        let day_start = 1_705_276_800;
+        let at = |utc: u64| UNIX_EPOCH + Duration::from_secs(utc);
+        let pacific_midnight = at(day_start + 8 * 3600);
+
+        // 23:59:59 PST is one second before the reset
+        assert_eq!(
+            next_pacific_midnight(at(day_start + 8 * 3600 - 1)),
+            pacific_midnight
+        );
+        // Midnight itself already belongs to the new day
+        assert_eq!(
+            next_pacific_midnight(pacific_midnight),
+            at(day_start + 8 * 3600 + 86_400)
+        );
+    }
+
+    // Attempt that fails with `errors[key]` when there is one and records every key it was given
+    fn scripted<'a>(
+        tried: &'a Mutex<Vec<usize>>,
+        errors: impl Fn(usize) -> Option<MelodyError> + 'a,
+    ) -> impl Fn(usize) -> std::future::Ready<MelodyResult<usize>> + 'a {
+        move |key| {
+            tried.lock().unwrap().push(key);
+            std::future::ready(match errors(key) {
+                Some(error) => Err(error),
+                None => Ok(key),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn sequential_fails_over_to_the_next_key() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |key| {
+            (key == 0).then(|| MelodyError::QuotaExhausted("YouTube".into()))
+        });
+
+        assert_eq!(sequential(&[0, 1, 2], 0, attempt).await.unwrap(), 1);
+        assert_eq!(*tried.lock().unwrap(), [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn sequential_wraps_around_from_the_start_key() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |key| {
+            (key != 1).then(|| MelodyError::Upstream("YouTube returned 503".into()))
+        });
+
+        assert_eq!(sequential(&[0, 1, 2], 2, attempt).await.unwrap(), 1);
+        assert_eq!(*tried.lock().unwrap(), [2, 0, 1]);
+    }
+
+    #[tokio::test]
+    async fn sequential_stops_at_final_errors() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |_| Some(MelodyError::NotFound("Video".into())));
+
+        let result = sequential(&[0, 1, 2], 0, attempt).await;
+        assert!(matches!(result, Err(MelodyError::NotFound(_))));
+        assert_eq!(*tried.lock().unwrap(), [0]);
+    }
+
+    #[tokio::test]
+    async fn sequential_reports_the_most_relevant_error() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |key| {
+            Some(match key {
+                1 => MelodyError::QuotaExhausted("YouTube".into()),
+                _ => MelodyError::Upstream("YouTube returned 500".into()),
+            })
+        });
+
+        let result = sequential(&[0, 1, 2], 0, attempt).await;
+        assert!(matches!(result, Err(MelodyError::QuotaExhausted(_))));
+        assert_eq!(tried.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn hedged_fires_the_next_key_when_the_first_is_slow() {
+        let attempt = |key: usize| async move {
+            if key == 0 {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+            Ok::<_, MelodyError>(key)
+        };
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(1),
+            hedged(&[0, 1], Duration::from_millis(10), attempt),
+        )
+        .await;
+        assert_eq!(result.unwrap().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn hedged_replaces_a_failed_key_without_waiting() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |key| {
+            (key == 0).then(|| MelodyError::Upstream("YouTube returned 500".into()))
+        });
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(1),
+            hedged(&[0, 1], Duration::from_secs(60), attempt),
+        )
+        .await;
+        assert_eq!(result.unwrap().unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn hedged_stops_at_final_errors() {
+        let tried = Mutex::new(Vec::new());
+        let attempt = scripted(&tried, |_| Some(MelodyError::InvalidInput("bad".into())));
+
+        let result = hedged(&[0, 1], Duration::from_secs(60), attempt).await;
+        assert!(matches!(result, Err(MelodyError::InvalidInput(_))));
+        assert_eq!(*tried.lock().unwrap(), [0]);
+    }
+}
@@ -1,15 +1,64 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use base64::Engine;
 use futures::future::join_all;
 use mongodb::{bson::{doc, Document}, Client as MongoClient, Collection};
 use once_cell::sync::Lazy;
-use regex::Regex;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
-use std::{collections::HashMap, env, sync::Arc, time::Duration};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    env,
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::RwLock;
 
+mod cache;
+mod convert;
+mod duration;
+mod endpoint;
+mod error;
+mod input;
+mod keys;
+mod lru;
+mod normalize;
+mod scoring;
+
+pub use cache::{
+    CachedMatch, LayeredMatchCache, MatchCache, MemoryMatchCache, MongoMatchCache, MATCH_CACHE,
+};
+pub use convert::{
+    convert_album, convert_artist_discography, convert_artist_top_tracks, convert_playlist,
+    convert_song, youtube_url, CollectionOutcome, CollectionResponse, ConvertedItem, ItemError,
+    ItemStatus, MatchOptions, SongMatch,
+};
+pub use duration::parse_iso_duration;
+pub use endpoint::{
+    collection_handler, collection_response, conversion_error_response, error_response,
+    json_response, record_conversion, song_response, success_response, ConversionParams,
+    InstanceHealth, WithHealth,
+};
+pub use error::{MelodyError, MelodyResult};
+pub use input::{
+    is_short_link, parse_market, parse_spotify_input, resolve_spotify_input, SpotifyResource,
+    SpotifyResourceKind,
+};
+pub use keys::{
+    get_youtube_api_keys, KeyHealth, KeyStatus, KeyStrategy, YouTubeKeyConfig, YouTubeKeyPool,
+    CHANNELS_QUOTA_COST, KEY_STRATEGY, SEARCH_QUOTA_COST, VIDEOS_QUOTA_COST,
+    YOUTUBE_KEY_POOL,
+};
+pub use lru::{CacheStats, LruCache};
+pub use normalize::{normalize_title, title_tokens, token_similarity};
+pub use scoring::{
+    match_scorer, AlbumNameRule, ArtistInTitleRule, ChannelSubscribersRule, DurationRule,
+    IsrcRule, MatchPreference, MatchScorer, MatchThreshold, NameInTitleRule, OfficialTitleRule,
+    RuleScorer, ScoreSignal, ScoringContext, ScoringRule, ScoringWeights, TopicChannelRule,
+    TrackNumberRule, VariantRule, ViewCountRule, DEFAULT_MIN_CONFIDENCE, MATCH_SCORER,
+    MATCH_THRESHOLD, SCORING_FINGERPRINT,
+};
+
 // Global HTTP client with connection pooling for MAXIMUM SPEED
 pub static HTTP_CLIENT: Lazy<Client> = Lazy::new(|| {
     Client::builder()
@@ -34,6 +83,33 @@ pub static MONGO_CLIENT: Lazy<Arc<RwLock<Option<MongoClient>>>> = Lazy::new(|| {
     Arc::new(RwLock::new(None))
 });
 
+// Per-instance caches of what a conversion fetches, so repeats make no outbound calls
+pub static SONG_INFO_CACHE: Lazy<LruCache<String, SpotifySong>> = Lazy::new(LruCache::from_env);
+// Keyed by search query and region
+pub static SEARCH_CACHE: Lazy<LruCache<String, YouTubeSearchResponse>> =
+    Lazy::new(LruCache::from_env);
+pub static VIDEO_DETAILS_CACHE: Lazy<LruCache<String, VideoDetails>> =
+    Lazy::new(LruCache::from_env);
+// Playlist, album, top tracks and discography pages, keyed by URL
+pub static SPOTIFY_PAGE_CACHE: Lazy<LruCache<String, Value>> = Lazy::new(LruCache::from_env);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct LruCacheStats {
+    pub song_info: CacheStats,
+    pub search: CacheStats,
+    pub video_details: CacheStats,
+    pub spotify_pages: CacheStats,
+}
+
+pub fn lru_cache_stats() -> LruCacheStats {
+    LruCacheStats {
+        song_info: SONG_INFO_CACHE.stats(),
+        search: SEARCH_CACHE.stats(),
+        video_details: VIDEO_DETAILS_CACHE.stats(),
+        spotify_pages: SPOTIFY_PAGE_CACHE.stats(),
+    }
+}
+
 // Spotify API client with token caching for SPEED
 pub static SPOTIFY_CLIENT: Lazy<Arc<RwLock<SpotifyClient>>> =
     Lazy::new(|| Arc::new(RwLock::new(SpotifyClient::new())));
@@ -52,7 +128,7 @@ impl SpotifyClient {
         }
     }
 
-    pub async fn get_token(&mut self) -> Result<String> {
+    pub async fn get_token(&mut self) -> MelodyResult<String> {
         // Check if we have a valid cached token - FASTER validation
         if let (Some(token), Some(expires_at)) = (&self.token, &self.token_expires_at) {
             if expires_at > &std::time::Instant::now() {
@@ -61,10 +137,10 @@ impl SpotifyClient {
         }
 
         // Get new token with OPTIMIZED request
-        let client_id =
-            env::var("SPOTIPY_CLIENT_ID").map_err(|_| anyhow!("SPOTIPY_CLIENT_ID not found"))?;
+        let client_id = env::var("SPOTIPY_CLIENT_ID")
+            .map_err(|_| MelodyError::Config("SPOTIPY_CLIENT_ID not found".to_string()))?;
         let client_secret = env::var("SPOTIPY_CLIENT_SECRET")
-            .map_err(|_| anyhow!("SPOTIPY_CLIENT_SECRET not found"))?;
+            .map_err(|_| MelodyError::Config("SPOTIPY_CLIENT_SECRET not found".to_string()))?;
 
         let auth_string = format!("{}:{}", client_id, client_secret);
         let auth_header = format!(
@@ -82,10 +158,17 @@ impl SpotifyClient {
             .send()
             .await?;
 
+        if !response.status().is_success() {
+            return Err(MelodyError::Auth(format!(
+                "Spotify token endpoint returned {}",
+                response.status()
+            )));
+        }
+
         let token_response: Value = response.json().await?;
         let access_token = token_response["access_token"]
             .as_str()
-            .ok_or_else(|| anyhow!("No access token in response"))?
+            .ok_or_else(|| MelodyError::Auth("No access token in response".to_string()))?
             .to_string();
 
         let expires_in = token_response["expires_in"].as_u64().unwrap_or(3600);
@@ -100,55 +183,110 @@ impl SpotifyClient {
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct SpotifySong {
     pub name: String,
     pub artists: Vec<SpotifyArtist>,
     pub album: SpotifyAlbum,
     pub duration_ms: u32,
     pub id: String,
+    #[serde(default)]
+    pub external_ids: SpotifyExternalIds,
 }
 
-#[derive(Debug, Deserialize)]
+// International Standard Recording Code - the same recording keeps it on every platform
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct SpotifyExternalIds {
+    pub isrc: Option<String>,
+}
+
+// Everything search_track_yt knows about the Spotify track it is matching
+#[derive(Debug, Clone, Default)]
+pub struct TrackQuery {
+    pub name: String,
+    // Every credited artist, main artist first
+    pub artists: Vec<String>,
+    pub album: String,
+    pub duration_ms: u32,
+    // Set when converting a whole album - turns on the album name and track order signals
+    pub album_position: Option<u32>,
+    pub isrc: Option<String>,
+    // Music video or audio-only upload - changes the search query and the title/channel signals
+    pub prefer: MatchPreference,
+}
+
+impl TrackQuery {
+    pub fn new(name: &str, artists: &[SpotifyArtist], album: &str, duration_ms: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            artists: artists.iter().map(|artist| artist.name.clone()).collect(),
+            album: album.to_string(),
+            duration_ms,
+            album_position: None,
+            isrc: None,
+            prefer: MatchPreference::Any,
+        }
+    }
+
+    pub fn from_song(song: &SpotifySong) -> MelodyResult<Self> {
+        if song.artists.is_empty() {
+            return Err(no_artists_error());
+        }
+
+        Ok(Self {
+            isrc: song.external_ids.isrc.clone(),
+            ..Self::new(&song.name, &song.artists, &song.album.name, song.duration_ms)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct SpotifyArtist {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct SpotifyAlbum {
     pub name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct YouTubeSearchResponse {
     pub items: Vec<YouTubeVideo>,
     #[serde(rename = "pageInfo")]
     pub page_info: YouTubePageInfo,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct YouTubePageInfo {
     #[serde(rename = "totalResults")]
     pub total_results: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct YouTubeVideo {
     pub id: YouTubeVideoId,
     pub snippet: YouTubeSnippet,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct YouTubeVideoId {
     #[serde(rename = "videoId")]
     pub video_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct YouTubeSnippet {
     pub title: String,
+    #[serde(rename = "channelId", default)]
+    pub channel_id: String,
     #[serde(rename = "channelTitle")]
     pub channel_title: String,
+    #[serde(default)]
+    pub description: String,
+    // "none", "live" or "upcoming"
+    #[serde(rename = "liveBroadcastContent", default)]
+    pub live_broadcast_content: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -158,208 +296,569 @@ pub struct YouTubeVideoDetails {
 
 #[derive(Debug, Deserialize)]
 pub struct YouTubeVideoDetailsItem {
+    pub id: String,
     #[serde(rename = "contentDetails")]
     pub content_details: YouTubeContentDetails,
+    // search.list truncates descriptions, videos.list returns them in full
+    pub snippet: Option<YouTubeSnippet>,
+    pub status: Option<YouTubeVideoStatus>,
+    pub statistics: Option<YouTubeVideoStatistics>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YouTubeVideoStatistics {
+    // Sent as a string, and left out when the uploader hides it
+    #[serde(rename = "viewCount")]
+    pub view_count: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YouTubeChannels {
+    #[serde(default)]
+    pub items: Vec<YouTubeChannel>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YouTubeChannel {
+    pub id: String,
+    pub statistics: Option<YouTubeChannelStatistics>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YouTubeChannelStatistics {
+    // Sent as a string, and left out when the channel hides it
+    #[serde(rename = "subscriberCount")]
+    pub subscriber_count: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct YouTubeVideoStatus {
+    #[serde(default = "default_embeddable")]
+    pub embeddable: bool,
+}
+
+fn default_embeddable() -> bool {
+    true
+}
+
+// What one batched videos.list call tells us about a search result
+#[derive(Debug, Clone)]
+pub struct VideoDetails {
+    // None when YouTube sent a duration we could not parse
+    pub duration_ms: Option<u64>,
+    pub description: String,
+    pub live_broadcast_content: String,
+    pub embeddable: bool,
+    pub region_restriction: Option<YouTubeRegionRestriction>,
+    // None when the uploader hides it
+    pub view_count: Option<u64>,
+    pub channel_id: String,
+    // Of the uploading channel - None when it hides them or channels.list failed
+    pub channel_subscribers: Option<u64>,
+}
+
+impl Default for VideoDetails {
+    fn default() -> Self {
+        Self {
+            duration_ms: None,
+            description: String::new(),
+            live_broadcast_content: "none".to_string(),
+            embeddable: true,
+            region_restriction: None,
+            view_count: None,
+            channel_id: String::new(),
+            channel_subscribers: None,
+        }
+    }
+}
+
+impl VideoDetails {
+    // Whether a viewer in `region` (ISO 3166-1 alpha-2) can watch this video
+    pub fn available_in(&self, region: &str) -> bool {
+        match &self.region_restriction {
+            // An allow list wins - everything else is blocked
+            Some(YouTubeRegionRestriction {
+                allowed: Some(allowed),
+                ..
+            }) => allowed.iter().any(|code| code == region),
+            Some(YouTubeRegionRestriction {
+                blocked: Some(blocked),
+                ..
+            }) => !blocked.iter().any(|code| code == region),
+            _ => true,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct YouTubeContentDetails {
     pub duration: String,
+    #[serde(rename = "regionRestriction")]
+    pub region_restriction: Option<YouTubeRegionRestriction>,
 }
 
-// LIGHTNING FAST song info fetcher
-pub async fn get_song_info(song_id: &str) -> Result<SpotifySong> {
+#[derive(Debug, Clone, Deserialize)]
+pub struct YouTubeRegionRestriction {
+    pub allowed: Option<Vec<String>>,
+    pub blocked: Option<Vec<String>>,
+}
+
+// Cached Spotify token - the write lock is only held while the token is refreshed
+pub(crate) async fn spotify_token() -> MelodyResult<String> {
     let mut spotify_client = SPOTIFY_CLIENT.write().await;
     let token = spotify_client.get_token().await?;
     drop(spotify_client); // Release lock ASAP for speed
+    Ok(token)
+}
 
-    let url = format!("https://api.spotify.com/v1/tracks/{}", song_id);
+// Authenticated GET against the Spotify Web API. `resource` names the thing in a 404.
+pub(crate) async fn spotify_get<T: serde::de::DeserializeOwned>(
+    url: &str,
+    resource: &str,
+) -> MelodyResult<T> {
+    let token = spotify_token().await?;
     let response = HTTP_CLIENT
-        .get(&url)
+        .get(url)
         .header("Authorization", format!("Bearer {}", token))
         .send()
         .await?;
 
     if !response.status().is_success() {
-        return Err(anyhow!("Failed to fetch song info: {}", response.status()));
+        return Err(MelodyError::from_spotify_status(response.status(), resource));
     }
 
-    let song: SpotifySong = response.json().await?;
+    Ok(response.json().await?)
+}
+
+// spotify_get through SPOTIFY_PAGE_CACHE, for the pages a collection conversion walks
+pub(crate) async fn spotify_get_cached<T: serde::de::DeserializeOwned>(
+    url: &str,
+    resource: &str,
+) -> MelodyResult<T> {
+    let page = match SPOTIFY_PAGE_CACHE.get(url) {
+        Some(page) => page,
+        None => {
+            let page: Value = spotify_get(url, resource).await?;
+            SPOTIFY_PAGE_CACHE.insert(url.to_string(), page.clone());
+            page
+        }
+    };
+    Ok(serde_json::from_value(page)?)
+}
+
+// LIGHTNING FAST song info fetcher
+pub async fn get_song_info(song_id: &str) -> MelodyResult<SpotifySong> {
+    if let Some(song) = SONG_INFO_CACHE.get(song_id) {
+        return Ok(song);
+    }
+
+    let url = format!("https://api.spotify.com/v1/tracks/{}", song_id);
+    let song: SpotifySong = spotify_get(&url, "Song").await?;
+    SONG_INFO_CACHE.insert(song_id.to_string(), song.clone());
     Ok(song)
 }
 
-// ULTRA FAST YouTube duration parsing - converts ISO 8601 to milliseconds
-pub fn parse_iso_duration(duration: &str) -> u32 {
-    static DURATION_REGEX: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"PT(?:(\d+)H)?(?:(\d+)M)?(?:(\d+)S)?").unwrap());
+// Single GET against the YouTube Data API with the failure turned into a MelodyError
+async fn youtube_get<T: serde::de::DeserializeOwned>(url: &str) -> MelodyResult<T> {
+    let response = HTTP_CLIENT
+        .get(url)
+        .header("Accept", "application/json")
+        .header("Accept-Encoding", "gzip, deflate, br")
+        .send()
+        .await?;
 
-    let caps = match DURATION_REGEX.captures(duration) {
-        Some(caps) => caps,
-        None => return 0,
-    };
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json::<T>().await?);
+    }
 
-    let hours = caps
-        .get(1)
-        .and_then(|m| m.as_str().parse::<u32>().ok())
-        .unwrap_or(0);
-    let minutes = caps
-        .get(2)
-        .and_then(|m| m.as_str().parse::<u32>().ok())
-        .unwrap_or(0);
-    let seconds = caps
-        .get(3)
-        .and_then(|m| m.as_str().parse::<u32>().ok())
-        .unwrap_or(0);
-
-    (hours * 3600 + minutes * 60 + seconds) * 1000
-}
-
-// BLAZING FAST YouTube video duration fetcher with API key rotation
-pub async fn get_track_duration_yt(video_id: &str, api_keys: &[String]) -> Result<u32> {
-    for api_key in api_keys {
-        let url = format!(
-            "https://youtube.googleapis.com/youtube/v3/videos?part=contentDetails&key={}&id={}",
-            api_key, video_id
-        );
+    let body: Value = response.json().await.unwrap_or_default();
+    Err(classify_youtube_error(status, &body))
+}
 
-        match HTTP_CLIENT.get(&url).send().await {
-            Ok(response) if response.status().is_success() => {
-                if let Ok(details) = response.json::<YouTubeVideoDetails>().await {
-                    if let Some(item) = details.items.first() {
-                        return Ok(parse_iso_duration(&item.content_details.duration));
-                    }
-                }
+// YouTube explains failures in error.errors[].reason (and error.details[].reason for bad keys).
+// The reason is what tells an exhausted key apart from a dead one or a flaky network.
+fn classify_youtube_error(status: reqwest::StatusCode, body: &Value) -> MelodyError {
+    let reasons: Vec<&str> = ["errors", "details"]
+        .iter()
+        .filter_map(|field| body["error"][field].as_array())
+        .flatten()
+        .filter_map(|entry| entry["reason"].as_str())
+        .collect();
+    let has = |wanted: &[&str]| reasons.iter().any(|reason| wanted.contains(reason));
+
+    if has(&["quotaExceeded", "dailyLimitExceeded"]) {
+        MelodyError::QuotaExhausted("YouTube".to_string())
+    } else if has(&["keyInvalid", "API_KEY_INVALID", "keyExpired", "accessNotConfigured"]) {
+        MelodyError::Auth(format!("YouTube rejected the API key ({})", status))
+    } else if has(&["rateLimitExceeded", "userRateLimitExceeded"]) {
+        MelodyError::Upstream("YouTube rate limit hit".to_string())
+    } else {
+        MelodyError::Upstream(format!("YouTube returned {}", status))
+    }
+}
+
+// videos.list accepts at most this many comma-separated ids per call
+const MAX_VIDEO_IDS_PER_REQUEST: usize = 50;
+
+// BLAZING FAST batched details fetcher - one videos.list call per 50 ids, with API key rotation.
+// Videos YouTube no longer knows about are simply missing from the map.
+pub async fn get_video_details_yt(
+    video_ids: &[String],
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<HashMap<String, VideoDetails>> {
+    let mut details = HashMap::with_capacity(video_ids.len());
+    let mut missing = Vec::new();
+    for id in video_ids {
+        match VIDEO_DETAILS_CACHE.get(id) {
+            Some(cached) => {
+                details.insert(id.clone(), cached);
             }
-            _ => continue, // Try next API key
+            None => missing.push(id.clone()),
         }
     }
 
-    Err(anyhow!("Failed to get video duration with all API keys"))
+    // Only the videos we have not seen lately cost a call
+    let chunk_tasks: Vec<_> = missing
+        .chunks(MAX_VIDEO_IDS_PER_REQUEST)
+        .map(|chunk| fetch_details_chunk(chunk.join(","), keys))
+        .collect();
+
+    let mut fetched = Vec::with_capacity(missing.len());
+    for chunk in join_all(chunk_tasks).await {
+        fetched.extend(chunk?);
+    }
+
+    // Subscriber counts of the uploaders - a weak signal, never worth failing the search over
+    let channel_ids: Vec<String> = fetched
+        .iter()
+        .map(|(_, video)| video.channel_id.clone())
+        .filter(|id| !id.is_empty())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let subscribers = get_channel_subscribers_yt(&channel_ids, keys)
+        .await
+        .unwrap_or_default();
+
+    for (id, mut video) in fetched {
+        video.channel_subscribers = subscribers.get(&video.channel_id).copied();
+        VIDEO_DETAILS_CACHE.insert(id.clone(), video.clone());
+        details.insert(id, video);
+    }
+
+    Ok(details)
 }
 
-// GODLY FAST YouTube search with custom accuracy scoring - ULTRA OPTIMIZED
-pub async fn search_track_yt(
-    song_name: &str,
-    artist_name: &str,
-    album_name: &str,
-    song_duration: u32,
-    api_keys: &[String],
-) -> Result<String> {
-    // OPTIMIZED search query construction
-    let search_query = format!(
-        "{} {} {} Official Audio",
-        song_name, album_name, artist_name
-    );
+// Subscriber counts by channel id, one channels.list call per 50 channels.
+// Channels that hide their count are missing from the map.
+pub async fn get_channel_subscribers_yt(
+    channel_ids: &[String],
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<HashMap<String, u64>> {
+    let chunk_tasks: Vec<_> = channel_ids
+        .chunks(MAX_VIDEO_IDS_PER_REQUEST)
+        .map(|chunk| {
+            let joined_ids = chunk.join(",");
+            keys.call(CHANNELS_QUOTA_COST, move |api_key| {
+                let url = format!(
+                    "https://youtube.googleapis.com/youtube/v3/channels?part=statistics&key={}&id={}",
+                    api_key, joined_ids
+                );
+                async move { youtube_get::<YouTubeChannels>(&url).await }
+            })
+        })
+        .collect();
 
-    // PARALLEL API key attempts for MAXIMUM SPEED
-    let search_futures: Vec<_> = api_keys.iter().enumerate().map(|(i, api_key)| {
-        let search_query = search_query.clone();
-        let api_key = api_key.clone();
-        async move {
-            let url = format!(
-                "https://youtube.googleapis.com/youtube/v3/search?part=snippet&q={}&type=video&maxResults=10&key={}",
-                urlencoding::encode(&search_query),
-                api_key
-            );
-
-            // FASTER request with explicit headers
-            let response = HTTP_CLIENT
-                .get(&url)
-                .header("Accept", "application/json")
-                .header("Accept-Encoding", "gzip, deflate, br")
-                .send()
-                .await;
-
-            match response {
-                Ok(resp) if resp.status().is_success() => {
-                    if let Ok(search_result) = resp.json::<YouTubeSearchResponse>().await {
-                        if !search_result.items.is_empty() {
-                            return Some((i, search_result));
-                        }
-                    }
-                }
-                _ => {}
-            }
-            None
-        }
-    }).collect();
+    let mut subscribers = HashMap::with_capacity(channel_ids.len());
+    for chunk in join_all(chunk_tasks).await {
+        subscribers.extend(chunk?.items.into_iter().filter_map(|channel| {
+            let count = channel.statistics?.subscriber_count?.parse().ok()?;
+            Some((channel.id, count))
+        }));
+    }
+    Ok(subscribers)
+}
 
-    // Wait for FIRST successful response (fastest API key wins)
-    let search_results = join_all(search_futures).await;
-    
-    // Get the first successful result
-    let search_result = search_results
+// Durations only - see get_video_details_yt
+pub async fn get_durations_yt(
+    video_ids: &[String],
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<HashMap<String, u64>> {
+    Ok(get_video_details_yt(video_ids, keys)
+        .await?
         .into_iter()
-        .find_map(|result| result)
-        .ok_or_else(|| anyhow!("Failed to search YouTube with all API keys"))?;
+        .filter_map(|(id, details)| Some((id, details.duration_ms?)))
+        .collect())
+}
 
-    let (_winning_api_index, search_data) = search_result;
+async fn fetch_details_chunk(
+    joined_ids: String,
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<Vec<(String, VideoDetails)>> {
+    // Extra parts cost nothing - videos.list is 1 unit whatever it returns
+    let details = keys.call(VIDEOS_QUOTA_COST, |api_key| {
+        let url = format!(
+            "https://youtube.googleapis.com/youtube/v3/videos?part=contentDetails,snippet,status,statistics&key={}&id={}",
+            api_key, joined_ids
+        );
+        async move { youtube_get::<YouTubeVideoDetails>(&url).await }
+    })
+    .await?;
 
-    // ULTRA PARALLEL duration fetching - ALL at once
-    let duration_tasks: Vec<_> = search_data
+    Ok(details
         .items
-        .iter()
+        .into_iter()
         .map(|item| {
-            let video_id = item.id.video_id.clone();
-            let api_keys = api_keys.to_vec();
-            async move {
-                get_track_duration_yt(&video_id, &api_keys).await.unwrap_or(0)
-            }
+            let (description, live_broadcast_content, channel_id) = match item.snippet {
+                Some(snippet) => (
+                    snippet.description,
+                    snippet.live_broadcast_content,
+                    snippet.channel_id,
+                ),
+                None => Default::default(),
+            };
+            let details = VideoDetails {
+                duration_ms: parse_iso_duration(&item.content_details.duration).ok(),
+                description,
+                live_broadcast_content,
+                embeddable: !matches!(item.status, Some(YouTubeVideoStatus { embeddable: false })),
+                region_restriction: item.content_details.region_restriction,
+                view_count: item
+                    .statistics
+                    .and_then(|statistics| statistics.view_count)
+                    .and_then(|views| views.parse().ok()),
+                channel_id,
+                channel_subscribers: None,
+            };
+            (item.id, details)
         })
-        .collect();
+        .collect())
+}
 
-    let durations = join_all(duration_tasks).await;
+// Single video convenience wrapper around get_durations_yt
+pub async fn get_track_duration_yt(video_id: &str, keys: &YouTubeKeyPool) -> MelodyResult<u64> {
+    get_durations_yt(&[video_id.to_string()], keys)
+        .await?
+        .remove(video_id)
+        .ok_or_else(|| MelodyError::NotFound("YouTube video".to_string()))
+}
 
-    // LIGHTNING FAST ACCURACY SCORING with BETTER scoring algorithm
-    let mut best_score = -1;
-    let mut best_video_id = &search_data.items[0].id.video_id;
+// Longest a YouTube Short can be
+const SHORTS_MAX_MS: u64 = 3 * 60 * 1000;
 
-    for (i, item) in search_data.items.iter().enumerate() {
-        let mut score = 0;
+// search.list results per query - also the most candidates a caller can ask for
+pub const MAX_CANDIDATES: usize = 10;
 
-        // +3 for Topic channels (official artist channels) - INCREASED weight
-        if item.snippet.channel_title.contains("Topic") {
-            score += 3;
-        }
+// One scored search result
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub video_id: String,
+    pub title: String,
+    pub channel: String,
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view_count: Option<u64>,
+    pub score: f64,
+    // score relative to the best score this query could reach, 0-1
+    pub confidence: f64,
+    // Every signal that added points - only serialized when explaining a match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signals: Option<Vec<ScoreSignal>>,
+}
 
-        // +3 for Official Audio/Video - INCREASED weight  
-        if item.snippet.title.contains("Official Audio")
-            || item.snippet.title.contains("Official Video")
-            || item.snippet.title.contains("Full Audio Song")
-        {
-            score += 3;
-        }
+// GODLY FAST YouTube search with custom accuracy scoring - ULTRA OPTIMIZED
+pub async fn search_track_yt(
+    query: &TrackQuery,
+    keys: &YouTubeKeyPool,
+) -> MelodyResult<String> {
+    let scorer = match_scorer()?;
+    let options = SearchOptions {
+        limit: 1,
+        ..Default::default()
+    };
+    let Some(best) = search_track_candidates(query, keys, scorer.as_ref(), &options)
+        .await?
+        .into_iter()
+        .next()
+    else {
+        return Err(MelodyError::NoMatch(0.0));
+    };
 
-        // +1 for containing artist name in title
-        if item.snippet.title.to_lowercase().contains(&artist_name.to_lowercase()) {
-            score += 1;
-        }
+    // Nothing relevant is a NoMatch, never "whatever YouTube ranked first"
+    if !MATCH_THRESHOLD.accepts(best.score, best.confidence) {
+        return Err(MelodyError::NoMatch(best.confidence));
+    }
+    Ok(best.video_id)
+}
+
+fn no_artists_error() -> MelodyError {
+    MelodyError::Empty("This track has no credited artists, so it cannot be matched".to_string())
+}
+
+// OPTIMIZED search query construction - the ISRC finds uploads that list it in their description
+pub fn build_search_query(query: &TrackQuery) -> String {
+    let mut search_query = format!(
+        "{} {} {} {}",
+        query.name,
+        query.album,
+        query.artists.join(" "),
+        query.prefer.search_suffix()
+    );
+    if let Some(isrc) = &query.isrc {
+        search_query.push(' ');
+        search_query.push_str(isrc);
+    }
+    search_query
+}
+
+// How a search should be run, on top of what is being searched for
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    // Most candidates to return, at least 1
+    pub limit: usize,
+    // Only keep videos watchable in this ISO 3166-1 alpha-2 country
+    pub region: Option<String>,
+}
+
+// The best search results, best first. Ties go to the most watched upload.
+// Live streams, premieres, Shorts, non-embeddable and region-blocked videos never make the list,
+// so it is empty when every result was one of those, or when the search found nothing.
+pub async fn search_track_candidates(
+    query: &TrackQuery,
+    keys: &YouTubeKeyPool,
+    scorer: &dyn MatchScorer,
+    options: &SearchOptions,
+) -> MelodyResult<Vec<Candidate>> {
+    // Without an artist the search is a guess - fail cleanly instead
+    if query.artists.is_empty() {
+        return Err(no_artists_error());
+    }
 
-        // +1 for containing song name in title
-        if item.snippet.title.to_lowercase().contains(&song_name.to_lowercase()) {
-            score += 1;
+    let search_query = build_search_query(query);
+
+    // Let YouTube drop non-embeddable and region-blocked videos up front where it can
+    let region_param = options
+        .region
+        .as_ref()
+        .map(|region| format!("&regionCode={}", region))
+        .unwrap_or_default();
+
+    let cache_key = format!("{}{}", search_query, region_param);
+    let search_data = match SEARCH_CACHE.get(&cache_key) {
+        Some(cached) => cached,
+        None => {
+            // One key per search - a search costs 100 quota units, so never fan out to every key
+            let search_data = keys.call(SEARCH_QUOTA_COST, |api_key| {
+                let url = format!(
+                    "https://youtube.googleapis.com/youtube/v3/search?part=snippet&q={}&type=video&videoEmbeddable=true&maxResults={}{}&key={}",
+                    urlencoding::encode(&search_query),
+                    MAX_CANDIDATES,
+                    region_param,
+                    api_key
+                );
+                async move { youtube_get::<YouTubeSearchResponse>(&url).await }
+            })
+            .await?;
+            SEARCH_CACHE.insert(cache_key, search_data.clone());
+            search_data
         }
+    };
 
-        // +7 for PERFECT duration match (within 1 second) - INCREASED weight
-        let video_duration = durations[i];
-        if video_duration > 0 {
-            let duration_diff = ((video_duration as i64) - (song_duration as i64)).abs();
-            if duration_diff <= 1000 {
-                score += 7;  // Perfect match
-            } else if duration_diff <= 2000 {
-                score += 5;  // Very close match
-            } else if duration_diff <= 5000 {
-                score += 2;  // Close match
+    // Nothing found is a NoMatch for this track, not an error for the whole collection
+    if search_data.items.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // ONE batched details lookup for every result - 1 quota unit instead of 10.
+    // Without it the Shorts, region and duration checks cannot run, and a pick made blind would
+    // be cached for weeks - fail the search so it is retried instead (the search itself is cached).
+    let video_ids: Vec<String> = search_data
+        .items
+        .iter()
+        .map(|item| item.id.video_id.clone())
+        .collect();
+    let details = get_video_details_yt(&video_ids, keys).await?;
+
+    let max_score = scorer.max_score(query);
+    let mut candidates: Vec<Candidate> = search_data
+        .items
+        .into_iter()
+        .filter(|item| {
+            let video = details.get(&item.id.video_id);
+            is_playable(query, &item.snippet, video, options.region.as_deref())
+        })
+        .map(|item| {
+            let video = details.get(&item.id.video_id);
+            let signals = scorer.score(&ScoringContext {
+                query,
+                snippet: &item.snippet,
+                video,
+            });
+            let score: f64 = signals.iter().map(|signal| signal.points).sum();
+            Candidate {
+                video_id: item.id.video_id,
+                title: item.snippet.title,
+                channel: item.snippet.channel_title,
+                duration_ms: video.and_then(|video| video.duration_ms),
+                view_count: video.and_then(|video| video.view_count),
+                score,
+                confidence: if max_score > 0.0 {
+                    (score / max_score).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                },
+                signals: Some(signals),
             }
-        }
+        })
+        .collect();
 
-        if score > best_score {
-            best_score = score;
-            best_video_id = &item.id.video_id;
-        }
+    // Equal scores go to the most watched upload rather than a low-view re-upload
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.view_count.cmp(&a.view_count))
+    });
+    candidates.truncate(options.limit.max(1));
+    Ok(candidates)
+}
+
+// Whether an embedded player could actually play this result as the song
+fn is_playable(
+    query: &TrackQuery,
+    snippet: &YouTubeSnippet,
+    video: Option<&VideoDetails>,
+    region: Option<&str>,
+) -> bool {
+    // Search results carry liveBroadcastContent too, so this holds for videos YouTube sent no
+    // details for
+    let live_broadcast = video.map_or(&snippet.live_broadcast_content, |video| {
+        &video.live_broadcast_content
+    });
+    if live_broadcast == "live" || live_broadcast == "upcoming" {
+        return false;
     }
 
-    Ok(best_video_id.clone())
+    let Some(video) = video else {
+        return true;
+    };
+
+    video.embeddable
+        && !matches!(region, Some(region) if !video.available_in(region))
+        && !is_short(query, snippet, video)
+}
+
+// Shorts have no API flag - go by the hashtag, or a clip far shorter than the song
+fn is_short(query: &TrackQuery, snippet: &YouTubeSnippet, video: &VideoDetails) -> bool {
+    let tagged = |text: &str| text.to_lowercase().contains("#shorts");
+    if tagged(&snippet.title) || tagged(&video.description) {
+        return true;
+    }
+
+    match video.duration_ms {
+        Some(ms) if ms > 0 => ms <= SHORTS_MAX_MS && u64::from(query.duration_ms) > 2 * ms,
+        _ => false,
+    }
 }
 
 // Async function to get or initialize MongoDB client - OPTIMIZED FOR SPEED
@@ -444,52 +943,13 @@ pub async fn update_analytics(songs_converted: i32, playlists_converted: i32) ->
     Ok(())
 }
 
-pub fn get_youtube_api_keys() -> Vec<String> {
-    let mut keys = Vec::new();
-
-    if let Ok(key) = env::var("YOUTUBE_API_KEY") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY2") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY3") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY4") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY5") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY6") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY7") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY8") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY9") {
-        keys.push(key);
-    }
-    if let Ok(key) = env::var("YOUTUBE_API_KEY10") {
-        keys.push(key);
-    }
-
-    if keys.is_empty() {
-        keys.push("default".to_string());
-    }
-
-    keys
-}
-
 #[derive(Serialize)]
 pub struct ApiResponse<T> {
     pub status: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
     #[serde(flatten)]
     pub data: Option<T>,
 }
@@ -499,6 +959,17 @@ impl<T> ApiResponse<T> {
         Self {
             status: "success".to_string(),
             message: None,
+            code: None,
+            data: Some(data),
+        }
+    }
+
+    // Some of the work succeeded - used by playlists converted in partial mode
+    pub fn partial(data: T) -> Self {
+        Self {
+            status: "partial".to_string(),
+            message: None,
+            code: None,
             data: Some(data),
         }
     }
@@ -507,7 +978,37 @@ impl<T> ApiResponse<T> {
         ApiResponse {
             status: "error".to_string(),
             message: Some(message),
+            code: None,
             data: None,
         }
     }
+
+    // A collection failed as a whole because of one of its items
+    pub fn from_item_error(error: &ItemError) -> ApiResponse<()> {
+        ApiResponse {
+            status: "error".to_string(),
+            message: Some(error.message.clone()),
+            code: Some(error.code.to_string()),
+            data: None,
+        }
+    }
+
+    pub fn from_error(error: &MelodyError) -> ApiResponse<()> {
+        ApiResponse {
+            status: "error".to_string(),
+            message: Some(error.user_message()),
+            code: Some(error.code().to_string()),
+            data: None,
+        }
+    }
+
+    // Same status, message and code with other fields next to them
+    pub fn with_data<U>(self, data: U) -> ApiResponse<U> {
+        ApiResponse {
+            status: self.status,
+            message: self.message,
+            code: self.code,
+            data: Some(data),
+        }
+    }
 }
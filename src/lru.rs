@@ -0,0 +1,222 @@
+use serde::Serialize;
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    env,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, MutexGuard,
+    },
+    time::{Duration, Instant},
+};
+
+const DEFAULT_CAPACITY: usize = 1_000;
+const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+// Size- and age-limited cache for one warm instance. The least recently used entry goes first
+// when full, and entries older than the TTL count as misses.
+pub struct LruCache<K, V> {
+    capacity: usize,
+    ttl: Duration,
+    state: Mutex<LruState<K, V>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct LruState<K, V> {
+    entries: HashMap<K, LruEntry<V>>,
+    // Last use -> key, oldest first
+    recency: BTreeMap<u64, K>,
+    clock: u64,
+}
+
+struct LruEntry<V> {
+    value: V,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+// What `health=yes` reports per cache - counters start over on a cold start
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            state: Mutex::new(LruState {
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                clock: 0,
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // MELODY_LRU_CAPACITY entries per cache (0 turns caching off), MELODY_LRU_TTL_SECS each
+    pub fn from_env() -> Self {
+        let capacity = env::var("MELODY_LRU_CAPACITY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_CAPACITY);
+        let ttl = env::var("MELODY_LRU_TTL_SECS")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+
+        Self::new(capacity, ttl)
+    }
+
+    // Nothing below panics halfway through an update, so a poisoned lock still guards a
+    // consistent cache - keep serving rather than fail every later request
+    fn state(&self) -> MutexGuard<'_, LruState<K, V>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut state = self.state();
+        let state = &mut *state;
+
+        let found = match state.entries.get_mut(key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.ttl => {
+                state.clock += 1;
+                let key = state.recency.remove(&entry.last_used);
+                entry.last_used = state.clock;
+                if let Some(key) = key {
+                    state.recency.insert(state.clock, key);
+                }
+                Some(entry.value.clone())
+            }
+            Some(entry) => {
+                // Expired - drop it now rather than wait for eviction
+                let last_used = entry.last_used;
+                state.entries.remove(key);
+                state.recency.remove(&last_used);
+                None
+            }
+            None => None,
+        };
+
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut state = self.state();
+        let state = &mut *state;
+        state.clock += 1;
+
+        if let Some(previous) = state.entries.remove(&key) {
+            state.recency.remove(&previous.last_used);
+        } else if state.entries.len() >= self.capacity {
+            if let Some((_, oldest)) = state.recency.pop_first() {
+                state.entries.remove(&oldest);
+            }
+        }
+
+        state.recency.insert(state.clock, key.clone());
+        state.entries.insert(
+            key,
+            LruEntry {
+                value,
+                inserted_at: Instant::now(),
+                last_used: state.clock,
+            },
+        );
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.state().entries.len(),
+            capacity: self.capacity,
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize) -> LruCache<String, u32> {
+        LruCache::new(capacity, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_entry() {
+        let cache = cache(2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        // Reading "a" makes "b" the oldest
+        assert_eq!(cache.get("a"), Some(1));
+        cache.insert("c".to_string(), 3);
+
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(3));
+    }
+
+    #[test]
+    fn replacing_a_key_does_not_evict() {
+        let cache = cache(2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        cache.insert("a".to_string(), 10);
+
+        assert_eq!(cache.get("a"), Some(10));
+        assert_eq!(cache.get("b"), Some(2));
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[test]
+    fn expired_entries_are_misses() {
+        let cache = LruCache::new(2, Duration::ZERO);
+        cache.insert("a".to_string(), 1);
+
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = cache(2);
+        cache.insert("a".to_string(), 1);
+        cache.get("a");
+        cache.get("a");
+        cache.get("b");
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+    }
+
+    #[test]
+    fn zero_capacity_caches_nothing() {
+        let cache = cache(0);
+        cache.insert("a".to_string(), 1);
+
+        assert_eq!(cache.get("a"), None);
+    }
+}
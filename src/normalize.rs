@@ -0,0 +1,236 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+
+// "(feat. X)", "[ft. X]", "(with X)"
+static FEATURING_BRACKETED: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)[(\[]\s*(?:feat\.?|ft\.?|featuring|with)\s[^)\]]*[)\]]").unwrap()
+});
+
+// "Song feat. X - Official Video" - up to the next separator
+static FEATURING_INLINE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(?:feat\.?|ft\.?|featuring)\s[^()\[\]|-]*").unwrap());
+
+// "(Remastered 2011)", "[2009 Remaster]"
+static REMASTER_BRACKETED: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)[(\[][^)\]]*\bremaster(?:ed)?\b[^)\]]*[)\]]").unwrap());
+
+// Spotify's "Song - Remastered 2011" / "Song - 2011 Remaster"
+static REMASTER_SUFFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\s-\s[^-]*\bremaster(?:ed)?\b[^-]*$").unwrap());
+
+// Lowercase, accent-free, punctuation-free form of a Spotify or YouTube title,
+// without featured artists or remaster notes
+pub fn normalize_title(title: &str) -> String {
+    let title = FEATURING_BRACKETED.replace_all(title, " ");
+    let title = REMASTER_BRACKETED.replace_all(&title, " ");
+    let title = REMASTER_SUFFIX.replace_all(&title, " ");
+    let title = FEATURING_INLINE.replace_all(&title, " ");
+
+    let mut normalized = String::with_capacity(title.len());
+    for c in fold_accents(&title.to_lowercase()).chars() {
+        match c {
+            '&' => normalized.push_str(" and "),
+            // "Don't" and "Dont" should be the same token
+            '\'' | '’' => {}
+            c if c.is_alphanumeric() => push_folded(&mut normalized, c),
+            _ => normalized.push(' '),
+        }
+    }
+
+    normalized.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+// Decompose, drop the Latin diacritics (U+0300-U+036F: acute, cedilla, comma below, Vietnamese
+// tone marks...), then recompose so scripts that rely on other combining marks stay intact
+fn fold_accents(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !('\u{0300}'..='\u{036F}').contains(c))
+        .nfc()
+        .collect()
+}
+
+pub fn title_tokens(title: &str) -> HashSet<String> {
+    normalize_title(title)
+        .split(' ')
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Share of `needle`'s tokens found in `haystack`, 0-1. One-sided on purpose:
+// YouTube titles add the artist, "Official Video" and the like, which should not cost anything.
+pub fn token_similarity(needle: &str, haystack: &str) -> f64 {
+    let needle = title_tokens(needle);
+    if needle.is_empty() {
+        return 0.0;
+    }
+
+    let haystack = title_tokens(haystack);
+    needle.intersection(&haystack).count() as f64 / needle.len() as f64
+}
+
+// Letters with no decomposition -> their usual Latin spelling, everything else as is
+fn push_folded(out: &mut String, c: char) {
+    let folded = match c {
+        'đ' => "d",
+        'ı' => "i",
+        'ł' => "l",
+        'ø' => "o",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        _ => {
+            out.push(c);
+            return;
+        }
+    };
+    out.push_str(folded);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lowercases_and_drops_punctuation() {
+        assert_eq!(normalize_title("HELLO, World!"), "hello world");
+        assert_eq!(normalize_title("  Mr.   Brightside  "), "mr brightside");
+        assert_eq!(
+            normalize_title("Song - Official Video"),
+            "song official video"
+        );
+        assert_eq!(normalize_title("!!!"), "");
+    }
+
+    #[test]
+    fn reads_ampersand_as_and_and_drops_apostrophes() {
+        assert_eq!(normalize_title("Simon & Garfunkel"), "simon and garfunkel");
+        assert_eq!(normalize_title("Don't Stop Me Now"), "dont stop me now");
+        assert_eq!(normalize_title("Don’t Stop"), "dont stop");
+    }
+
+    #[test]
+    fn strips_featured_artists() {
+        assert_eq!(normalize_title("Stay (feat. Justin Bieber)"), "stay");
+        assert_eq!(normalize_title("Stay [ft. Justin Bieber]"), "stay");
+        assert_eq!(normalize_title("Stay (with Justin Bieber)"), "stay");
+        assert_eq!(normalize_title("Stay (Featuring Justin Bieber)"), "stay");
+        assert_eq!(
+            normalize_title("Stay feat. Justin Bieber - Official Video"),
+            "stay official video"
+        );
+        // "with" only counts inside brackets
+        assert_eq!(normalize_title("Dance With Me"), "dance with me");
+    }
+
+    #[test]
+    fn strips_remaster_notes() {
+        assert_eq!(
+            normalize_title("Here Comes The Sun - Remastered 2009"),
+            "here comes the sun"
+        );
+        assert_eq!(normalize_title("Let It Be - 2009 Remaster"), "let it be");
+        assert_eq!(normalize_title("Heroes (Remastered 2017)"), "heroes");
+        assert_eq!(normalize_title("Heroes [2017 Remaster]"), "heroes");
+        // Only as a note, not as part of the name
+        assert_eq!(normalize_title("Remaster"), "remaster");
+    }
+
+    #[test]
+    fn folds_precomposed_accents() {
+        assert_eq!(normalize_title("Beyoncé"), "beyonce");
+        assert_eq!(normalize_title("Sigur Rós"), "sigur ros");
+        assert_eq!(normalize_title("Mötley Crüe"), "motley crue");
+        assert_eq!(normalize_title("Ñandú"), "nandu");
+    }
+
+    #[test]
+    fn folds_accents_outside_a_fixed_table() {
+        // Romanian comma below
+        assert_eq!(normalize_title("Ștefan Bănică"), "stefan banica");
+        assert_eq!(normalize_title("Țară"), "tara");
+        // Vietnamese stacked tone marks
+        assert_eq!(
+            normalize_title("Sơn Tùng M-TP Lạc Trôi"),
+            "son tung m tp lac troi"
+        );
+        assert_eq!(
+            normalize_title("Đen Vâu - Bài Này Chill Phết"),
+            "den vau bai nay chill phet"
+        );
+        // Nordic and other letters without a decomposition
+        assert_eq!(
+            normalize_title("Ørjan Ålesund Æther"),
+            "orjan alesund aether"
+        );
+        assert_eq!(normalize_title("Łódź Straße"), "lodz strasse");
+    }
+
+    #[test]
+    fn folds_decomposed_input_without_splitting_tokens() {
+        // NFD input: "e" followed by a combining acute accent
+        assert_eq!(normalize_title("E\u{301}tude"), "etude");
+        assert_eq!(normalize_title("Beyonce\u{301}"), "beyonce");
+        assert_eq!(normalize_title("E\u{301}tude"), normalize_title("Étude"));
+    }
+
+    #[test]
+    fn keeps_other_scripts() {
+        assert_eq!(normalize_title("강남스타일"), "강남스타일");
+        assert_eq!(normalize_title("紅蓮華"), "紅蓮華");
+        // Japanese voiced marks survive decomposition
+        assert_eq!(normalize_title("がんばれ"), "がんばれ");
+        assert_eq!(normalize_title("Кино - Группа крови"), "кино группа крови");
+    }
+
+    #[test]
+    fn title_tokens_are_unique_words() {
+        let tokens = title_tokens("Love Me Love Me (feat. X)");
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.contains("love") && tokens.contains("me"));
+        assert!(title_tokens("  ...  ").is_empty());
+    }
+
+    #[test]
+    fn similarity_is_the_share_of_needle_tokens_found() {
+        assert_eq!(
+            token_similarity("Hello", "Adele - Hello (Official Video)"),
+            1.0
+        );
+        assert_eq!(
+            token_similarity("Rolling in the Deep", "Adele - Rolling Deep"),
+            0.5
+        );
+        assert_eq!(token_similarity("Hello", "Goodbye"), 0.0);
+    }
+
+    #[test]
+    fn similarity_is_one_sided() {
+        assert_eq!(token_similarity("Adele", "Adele Hello Official Video"), 1.0);
+        assert_eq!(
+            token_similarity("Adele Hello Official Video", "Adele"),
+            0.25
+        );
+    }
+
+    #[test]
+    fn similarity_ignores_case_accents_and_notes() {
+        assert_eq!(token_similarity("Beyoncé", "BEYONCE - Halo"), 1.0);
+        assert_eq!(
+            token_similarity(
+                "Here Comes The Sun - Remastered 2009",
+                "The Beatles - Here Comes The Sun"
+            ),
+            1.0
+        );
+    }
+
+    #[test]
+    fn empty_needle_has_no_similarity() {
+        assert_eq!(token_similarity("", "anything"), 0.0);
+        assert_eq!(token_similarity("???", "anything"), 0.0);
+    }
+}
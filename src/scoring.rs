@@ -0,0 +1,771 @@
+use crate::{
+    title_tokens, token_similarity, MelodyError, MelodyResult, TrackQuery, VideoDetails,
+    YouTubeSnippet,
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, sync::Arc};
+
+// Scorer used by every search, built once per instance from MELODY_SCORING_WEIGHTS(_FILE).
+// A broken weights config is kept as an error so requests report it instead of scoring silently
+// with defaults.
+pub static MATCH_SCORER: Lazy<Result<Arc<dyn MatchScorer>, String>> =
+    Lazy::new(|| match ScoringWeights::from_env() {
+        Ok(weights) => Ok(Arc::new(RuleScorer::from_weights(&weights)) as Arc<dyn MatchScorer>),
+        Err(MelodyError::Config(message)) => Err(message),
+        Err(other) => Err(other.to_string()),
+    });
+
+// Identifies the weights MATCH_SCORER was built from, so cached matches from another profile
+// (or another deployment sharing the cache) are never reused
+pub static SCORING_FINGERPRINT: Lazy<String> = Lazy::new(|| match ScoringWeights::from_env() {
+    Ok(weights) => weights.fingerprint(),
+    Err(_) => "invalid".to_string(),
+});
+
+// Server-wide default threshold from MELODY_MIN_CONFIDENCE / MELODY_MIN_SCORE
+pub static MATCH_THRESHOLD: Lazy<MatchThreshold> = Lazy::new(MatchThreshold::from_env);
+
+pub fn match_scorer() -> MelodyResult<Arc<dyn MatchScorer>> {
+    MATCH_SCORER
+        .as_ref()
+        .map(Arc::clone)
+        .map_err(|message| MelodyError::Config(message.clone()))
+}
+
+// Points one scoring signal gave a candidate
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoreSignal {
+    pub signal: &'static str,
+    pub points: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ScoreSignal {
+    pub fn new(signal: &'static str, points: f64) -> Self {
+        Self {
+            signal,
+            points,
+            detail: None,
+        }
+    }
+}
+
+// Everything a rule may look at for one search result
+pub struct ScoringContext<'a> {
+    pub query: &'a TrackQuery,
+    pub snippet: &'a YouTubeSnippet,
+    // From the batched videos.list call - missing if YouTube no longer knows the video
+    pub video: Option<&'a VideoDetails>,
+}
+
+impl ScoringContext<'_> {
+    // Full description when we have it, the truncated search snippet otherwise
+    pub fn description(&self) -> &str {
+        match self.video {
+            Some(video) if !video.description.is_empty() => &video.description,
+            _ => &self.snippet.description,
+        }
+    }
+
+    pub fn duration_ms(&self) -> Option<u64> {
+        self.video.and_then(|video| video.duration_ms)
+    }
+
+    pub fn view_count(&self) -> Option<u64> {
+        self.video.and_then(|video| video.view_count)
+    }
+
+    pub fn channel_subscribers(&self) -> Option<u64> {
+        self.video.and_then(|video| video.channel_subscribers)
+    }
+}
+
+// Turns a search result into a list of signals - the score is their sum
+pub trait MatchScorer: Send + Sync {
+    fn score(&self, ctx: &ScoringContext) -> Vec<ScoreSignal>;
+
+    // Best total this query could reach, used to normalize scores into a confidence
+    fn max_score(&self, query: &TrackQuery) -> f64;
+}
+
+// One independent signal. Return None when the rule does not apply.
+pub trait ScoringRule: Send + Sync {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal>;
+
+    // Most points this rule can add for `query` - 0 when it cannot fire
+    fn max_points(&self, query: &TrackQuery) -> f64;
+}
+
+// Default scorer - the sum of a list of rules
+pub struct RuleScorer {
+    rules: Vec<Box<dyn ScoringRule>>,
+}
+
+impl RuleScorer {
+    pub fn new() -> Self {
+        Self { rules: Vec::new() }
+    }
+
+    pub fn with_rule(mut self, rule: impl ScoringRule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    // The built-in rules with the given weights
+    pub fn from_weights(weights: &ScoringWeights) -> Self {
+        Self::new()
+            .with_rule(IsrcRule(weights.isrc_in_description))
+            .with_rule(TopicChannelRule(weights.topic_channel))
+            .with_rule(OfficialTitleRule(weights.official_title))
+            .with_rule(ArtistInTitleRule(weights.artist_in_title))
+            .with_rule(NameInTitleRule(weights.name_in_title))
+            .with_rule(AlbumNameRule(weights.album_name))
+            .with_rule(TrackNumberRule(weights.track_number))
+            .with_rule(DurationRule {
+                exact: weights.duration_exact,
+                close: weights.duration_close,
+                near: weights.duration_near,
+            })
+            .with_rule(VariantRule(weights.variant))
+            .with_rule(ViewCountRule(weights.view_count))
+            .with_rule(ChannelSubscribersRule(weights.channel_subscribers))
+    }
+}
+
+impl Default for RuleScorer {
+    fn default() -> Self {
+        Self::from_weights(&ScoringWeights::default())
+    }
+}
+
+impl MatchScorer for RuleScorer {
+    fn score(&self, ctx: &ScoringContext) -> Vec<ScoreSignal> {
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.score(ctx))
+            .filter(|signal| signal.points != 0.0)
+            .collect()
+    }
+
+    fn max_score(&self, query: &TrackQuery) -> f64 {
+        self.rules
+            .iter()
+            .map(|rule| rule.max_points(query).max(0.0))
+            .sum()
+    }
+}
+
+// Confidence below which a search means "not on YouTube", not "take the first result"
+pub const DEFAULT_MIN_CONFIDENCE: f64 = 0.25;
+
+// How good the best candidate must be to count as a match. Both limits apply when set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MatchThreshold {
+    pub min_confidence: f64,
+    pub min_score: Option<f64>,
+}
+
+impl Default for MatchThreshold {
+    fn default() -> Self {
+        Self {
+            min_confidence: DEFAULT_MIN_CONFIDENCE,
+            min_score: None,
+        }
+    }
+}
+
+impl MatchThreshold {
+    pub fn from_env() -> Self {
+        let min_confidence = env::var("MELODY_MIN_CONFIDENCE")
+            .ok()
+            .and_then(|value| value.parse::<f64>().ok())
+            .map(|value| value.clamp(0.0, 1.0))
+            .unwrap_or(DEFAULT_MIN_CONFIDENCE);
+        let min_score = env::var("MELODY_MIN_SCORE")
+            .ok()
+            .and_then(|value| value.parse().ok());
+
+        Self {
+            min_confidence,
+            min_score,
+        }
+    }
+
+    pub fn accepts(&self, score: f64, confidence: f64) -> bool {
+        confidence >= self.min_confidence && !matches!(self.min_score, Some(min) if score < min)
+    }
+}
+
+// Which kind of upload a caller wants when a song has both
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchPreference {
+    // The audio-only upload, ideally from the artist's "Topic" channel
+    Audio,
+    // The official music video
+    Video,
+    #[default]
+    Any,
+}
+
+impl MatchPreference {
+    // `?prefer=audio|video|any`
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "audio" => Some(Self::Audio),
+            "video" => Some(Self::Video),
+            "any" => Some(Self::Any),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Video => "video",
+            Self::Any => "any",
+        }
+    }
+
+    pub fn search_suffix(self) -> &'static str {
+        match self {
+            Self::Audio => "Official Audio",
+            Self::Video => "Official Music Video",
+            Self::Any => "Official Audio",
+        }
+    }
+
+    // Title markers of the uploads this preference wants
+    fn official_titles(self) -> &'static [&'static str] {
+        match self {
+            Self::Audio => &["Official Audio", "Full Audio Song"],
+            Self::Video => &["Official Video", "Official Music Video"],
+            Self::Any => &["Official Audio", "Official Video", "Full Audio Song"],
+        }
+    }
+}
+
+// Points per signal. Missing fields keep their default, so a profile only lists what it changes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScoringWeights {
+    pub isrc_in_description: f64,
+    pub topic_channel: f64,
+    pub official_title: f64,
+    pub artist_in_title: f64,
+    pub name_in_title: f64,
+    pub album_name: f64,
+    pub track_number: f64,
+    // Duration within 1s / 2s / 5s of the Spotify track
+    pub duration_exact: f64,
+    pub duration_close: f64,
+    pub duration_near: f64,
+    // Added (it is negative) for covers, remixes, live, karaoke, sped up... uploads
+    pub variant: f64,
+    // Reached at a billion views, on a log scale - small, so it mostly breaks ties
+    pub view_count: f64,
+    // Reached at ten million subscribers of the uploading channel, on a log scale
+    pub channel_subscribers: f64,
+}
+
+impl Default for ScoringWeights {
+    fn default() -> Self {
+        Self {
+            isrc_in_description: 10.0,
+            topic_channel: 3.0,
+            official_title: 3.0,
+            artist_in_title: 1.0,
+            name_in_title: 1.0,
+            album_name: 1.0,
+            track_number: 1.0,
+            duration_exact: 7.0,
+            duration_close: 5.0,
+            duration_near: 2.0,
+            variant: -8.0,
+            view_count: 0.5,
+            channel_subscribers: 0.5,
+        }
+    }
+}
+
+impl ScoringWeights {
+    // MELODY_SCORING_WEIGHTS holds the JSON itself, MELODY_SCORING_WEIGHTS_FILE names a JSON file
+    pub fn from_env() -> MelodyResult<Self> {
+        if let Ok(json) = env::var("MELODY_SCORING_WEIGHTS") {
+            return Self::from_json(&json, "MELODY_SCORING_WEIGHTS");
+        }
+
+        if let Ok(path) = env::var("MELODY_SCORING_WEIGHTS_FILE") {
+            let json = fs::read_to_string(&path).map_err(|e| {
+                MelodyError::Config(format!("cannot read scoring weights file {}: {}", path, e))
+            })?;
+            return Self::from_json(&json, &path);
+        }
+
+        Ok(Self::default())
+    }
+
+    // FNV-1a of the serialized weights - unlike std's hasher it is stable across Rust releases,
+    // so every deployment computes the same value for the same profile
+    pub fn fingerprint(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        let hash = json.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{:016x}", hash)
+    }
+
+    fn from_json(json: &str, source: &str) -> MelodyResult<Self> {
+        serde_json::from_str(json).map_err(|e| {
+            MelodyError::Config(format!("invalid scoring weights in {}: {}", source, e))
+        })
+    }
+}
+
+// Labels put the ISRC in the full description - the exact recording
+pub struct IsrcRule(pub f64);
+
+impl ScoringRule for IsrcRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let isrc = ctx.query.isrc.as_deref()?;
+        description_has_isrc(ctx.description(), isrc)
+            .then(|| ScoreSignal::new("isrc_in_description", self.0))
+    }
+
+    // A bonus on top of a full match - most official uploads never print the ISRC, and counting
+    // it would push their confidence below the threshold
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        0.0
+    }
+}
+
+// "Artist - Topic" channels are YouTube's official auto-generated artist channels.
+// They only carry audio, so they earn nothing when the caller wants the music video.
+pub struct TopicChannelRule(pub f64);
+
+impl ScoringRule for TopicChannelRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        (ctx.query.prefer != MatchPreference::Video && ctx.snippet.channel_title.contains("Topic"))
+            .then(|| ScoreSignal::new("topic_channel", self.0))
+    }
+
+    fn max_points(&self, query: &TrackQuery) -> f64 {
+        if query.prefer == MatchPreference::Video {
+            0.0
+        } else {
+            self.0
+        }
+    }
+}
+
+// "Official Audio" or "Official Video" in the title, whichever the caller prefers
+pub struct OfficialTitleRule(pub f64);
+
+impl ScoringRule for OfficialTitleRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let marker = ctx
+            .query
+            .prefer
+            .official_titles()
+            .iter()
+            .find(|marker| ctx.snippet.title.contains(*marker))?;
+
+        Some(ScoreSignal {
+            detail: Some(marker.to_string()),
+            ..ScoreSignal::new("official_title", self.0)
+        })
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.0
+    }
+}
+
+// Scaled by how much of the best-matching credited artist's name the title or channel contains,
+// so a collaborator's or featured artist's upload counts as much as the main artist's
+pub struct ArtistInTitleRule(pub f64);
+
+impl ScoringRule for ArtistInTitleRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let haystack = format!("{} {}", ctx.snippet.title, ctx.snippet.channel_title);
+        let (artist, similarity) = ctx
+            .query
+            .artists
+            .iter()
+            .map(|artist| (artist, token_similarity(artist, &haystack)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        similarity_signal("artist_in_title", self.0, similarity).map(|signal| ScoreSignal {
+            detail: Some(format!("{} ({})", artist, signal.detail.unwrap_or_default())),
+            ..signal
+        })
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.0
+    }
+}
+
+// Scaled by how much of the song name the normalized title contains, so
+// "Song - Remastered 2011 (feat. X)" still fully matches "Artist - Song (Official Video)"
+pub struct NameInTitleRule(pub f64);
+
+impl ScoringRule for NameInTitleRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        similarity_signal(
+            "name_in_title",
+            self.0,
+            token_similarity(&ctx.query.name, &ctx.snippet.title),
+        )
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.0
+    }
+}
+
+// Album conversions only: every word of the album name in the title or description.
+// Whole tokens, so short names like "x" or "21" do not match inside other words.
+pub struct AlbumNameRule(pub f64);
+
+impl ScoringRule for AlbumNameRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        ctx.query.album_position?;
+        let album = title_tokens(&ctx.query.album);
+        if album.is_empty() {
+            return None;
+        }
+
+        let contains_album = |text: &str| album.is_subset(&title_tokens(text));
+        (contains_album(&ctx.snippet.title) || contains_album(ctx.description()))
+            .then(|| ScoreSignal::new("album_name", self.0))
+    }
+
+    fn max_points(&self, query: &TrackQuery) -> f64 {
+        if query.album_position.is_some() {
+            self.0
+        } else {
+            0.0
+        }
+    }
+}
+
+// Album conversions only: a title numbered like the track ("03. Song", "Track 3")
+pub struct TrackNumberRule(pub f64);
+
+impl ScoringRule for TrackNumberRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let position = ctx.query.album_position?;
+        has_track_number(&ctx.snippet.title, position)
+            .then(|| ScoreSignal::new("track_number", self.0))
+    }
+
+    fn max_points(&self, query: &TrackQuery) -> f64 {
+        if query.album_position.is_some() {
+            self.0
+        } else {
+            0.0
+        }
+    }
+}
+
+// Points for a video within 1s (exact), 2s (close) or 5s (near) of the Spotify duration
+pub struct DurationRule {
+    pub exact: f64,
+    pub close: f64,
+    pub near: f64,
+}
+
+impl ScoringRule for DurationRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        // Live streams report zero
+        let video_duration = ctx.duration_ms().filter(|&ms| ms > 0)?;
+
+        let duration_diff = video_duration.abs_diff(u64::from(ctx.query.duration_ms));
+        let points = match duration_diff {
+            0..=1000 => self.exact,
+            1001..=2000 => self.close,
+            2001..=5000 => self.near,
+            _ => return None,
+        };
+
+        Some(ScoreSignal {
+            detail: Some(format!("{}ms off", duration_diff)),
+            ..ScoreSignal::new("duration", points)
+        })
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.exact.max(self.close).max(self.near)
+    }
+}
+
+fn similarity_signal(signal: &'static str, weight: f64, similarity: f64) -> Option<ScoreSignal> {
+    (similarity > 0.0).then(|| ScoreSignal {
+        detail: Some(format!("{:.0}% of tokens", similarity * 100.0)),
+        ..ScoreSignal::new(signal, weight * similarity)
+    })
+}
+
+// Alternate versions of a song. Each is only penalized when the Spotify track is not itself one.
+static VARIANT_MARKERS: Lazy<Vec<(&'static str, Regex)>> = Lazy::new(|| {
+    [
+        ("cover", r"\bcover\b"),
+        ("remix", r"\b(?:remix|rmx)\b"),
+        ("live", r"\blive\b"),
+        ("karaoke", r"\bkaraoke\b"),
+        ("nightcore", r"\bnightcore\b"),
+        ("sped up", r"\bsped[\s-]*up\b"),
+        ("slowed", r"\bslowed\b"),
+        ("8d audio", r"\b8d\b"),
+        ("instrumental", r"\binstrumental\b"),
+    ]
+    .into_iter()
+    .map(|(marker, pattern)| (marker, Regex::new(&format!("(?i){}", pattern)).unwrap()))
+    .collect()
+});
+
+// Title or channel marks the upload as a variant the Spotify track name does not mention
+pub struct VariantRule(pub f64);
+
+impl ScoringRule for VariantRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let markers: Vec<&str> = VARIANT_MARKERS
+            .iter()
+            .filter(|(_, pattern)| {
+                let upload_has_it = pattern.is_match(&ctx.snippet.title)
+                    || pattern.is_match(&ctx.snippet.channel_title);
+                upload_has_it && !pattern.is_match(&ctx.query.name)
+            })
+            .map(|(marker, _)| *marker)
+            .collect();
+
+        if markers.is_empty() {
+            return None;
+        }
+
+        Some(ScoreSignal {
+            detail: Some(markers.join(", ")),
+            ..ScoreSignal::new("variant", self.0)
+        })
+    }
+
+    // A penalty never raises the best reachable score
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        0.0
+    }
+}
+
+// Views at which ViewCountRule gives its full weight
+const FULL_WEIGHT_VIEWS: f64 = 1e9;
+
+// Widely watched uploads are more likely the legitimate one. Logarithmic, so 10k and 100k
+// views differ as much as 10M and 100M.
+pub struct ViewCountRule(pub f64);
+
+impl ScoringRule for ViewCountRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let views = ctx.view_count()?;
+        let share = ((views as f64 + 1.0).log10() / FULL_WEIGHT_VIEWS.log10()).min(1.0);
+
+        Some(ScoreSignal {
+            detail: Some(format!("{} views", views)),
+            ..ScoreSignal::new("view_count", self.0 * share)
+        })
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.0
+    }
+}
+
+// Subscribers at which ChannelSubscribersRule gives its full weight
+const FULL_WEIGHT_SUBSCRIBERS: f64 = 1e7;
+
+// An established channel is more likely the artist's or label's than a re-uploader's.
+// Logarithmic like ViewCountRule, and just as small.
+pub struct ChannelSubscribersRule(pub f64);
+
+impl ScoringRule for ChannelSubscribersRule {
+    fn score(&self, ctx: &ScoringContext) -> Option<ScoreSignal> {
+        let subscribers = ctx.channel_subscribers()?;
+        let share =
+            ((subscribers as f64 + 1.0).log10() / FULL_WEIGHT_SUBSCRIBERS.log10()).min(1.0);
+
+        Some(ScoreSignal {
+            detail: Some(format!("{} subscribers", subscribers)),
+            ..ScoreSignal::new("channel_subscribers", self.0 * share)
+        })
+    }
+
+    fn max_points(&self, _query: &TrackQuery) -> f64 {
+        self.0
+    }
+}
+
+// ISRCs are printed with or without dashes ("USUM71703861" / "US-UM7-17-03861")
+fn description_has_isrc(description: &str, isrc: &str) -> bool {
+    let isrc = isrc.replace('-', "").to_uppercase();
+    !isrc.is_empty() && description.to_uppercase().replace('-', "").contains(&isrc)
+}
+
+// "03. Song", "3 - Song", "(Track 3)" - album rips often carry the track number
+fn has_track_number(title: &str, position: u32) -> bool {
+    static LEADING_NUMBER: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^\s*0*(\d+)\s*[.)\-:]").unwrap());
+    static TRACK_LABEL: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?i)\btrack\s*0*(\d+)\b").unwrap());
+
+    [&*LEADING_NUMBER, &*TRACK_LABEL].iter().any(|pattern| {
+        pattern
+            .captures(title)
+            .and_then(|caps| caps[1].parse::<u32>().ok())
+            == Some(position)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(name: &str, artist: &str, album: &str) -> TrackQuery {
+        TrackQuery {
+            name: name.to_string(),
+            artists: vec![artist.to_string()],
+            album: album.to_string(),
+            duration_ms: 200_000,
+            ..Default::default()
+        }
+    }
+
+    fn snippet(title: &str, channel: &str, description: &str) -> YouTubeSnippet {
+        YouTubeSnippet {
+            title: title.to_string(),
+            channel_id: String::new(),
+            channel_title: channel.to_string(),
+            description: description.to_string(),
+            live_broadcast_content: "none".to_string(),
+        }
+    }
+
+    fn rule_points(rule: &dyn ScoringRule, query: &TrackQuery, snippet: &YouTubeSnippet) -> f64 {
+        let ctx = ScoringContext {
+            query,
+            snippet,
+            video: None,
+        };
+        rule.score(&ctx).map_or(0.0, |signal| signal.points)
+    }
+
+    #[test]
+    fn album_name_matches_whole_tokens_only() {
+        let query = TrackQuery {
+            album_position: Some(1),
+            ..query("Sing", "Ed Sheeran", "x")
+        };
+        let rule = AlbumNameRule(1.0);
+
+        assert_eq!(
+            rule_points(&rule, &query, &snippet("Ed Sheeran - Sing", "Ed Sheeran", "Next track")),
+            0.0
+        );
+        assert_eq!(
+            rule_points(&rule, &query, &snippet("Sing (from x)", "Ed Sheeran", "")),
+            1.0
+        );
+    }
+
+    #[test]
+    fn album_name_needs_every_token() {
+        let query = TrackQuery {
+            album_position: Some(1),
+            ..query("Hello", "Adele", "25 Deluxe")
+        };
+        let rule = AlbumNameRule(1.0);
+
+        assert_eq!(rule_points(&rule, &query, &snippet("Adele - Hello", "Adele", "25")), 0.0);
+        assert_eq!(
+            rule_points(&rule, &query, &snippet("Hello", "Adele", "From 25 (Deluxe)")),
+            1.0
+        );
+    }
+
+    #[test]
+    fn isrc_bonus_does_not_lower_confidence_without_it() {
+        let query = TrackQuery {
+            isrc: Some("GBAHS1600463".to_string()),
+            ..query("Shape of You", "Ed Sheeran", "Divide")
+        };
+        let snippet = snippet("Ed Sheeran - Shape of You (Official Video)", "Ed Sheeran", "");
+        // No ISRC in the description, and 20s of intro the album version does not have
+        let video = VideoDetails {
+            duration_ms: Some(220_000),
+            view_count: Some(6_000_000_000),
+            ..Default::default()
+        };
+
+        let scorer = RuleScorer::default();
+        let ctx = ScoringContext {
+            query: &query,
+            snippet: &snippet,
+            video: Some(&video),
+        };
+        let score: f64 = scorer.score(&ctx).iter().map(|signal| signal.points).sum();
+        let confidence = score / scorer.max_score(&query);
+
+        assert!(MatchThreshold::default().accepts(score, confidence));
+    }
+
+    #[test]
+    fn fingerprint_changes_with_the_weights() {
+        let default = ScoringWeights::default();
+        let tuned = ScoringWeights {
+            topic_channel: 4.0,
+            ..ScoringWeights::default()
+        };
+
+        assert_eq!(default.fingerprint(), ScoringWeights::default().fingerprint());
+        assert_ne!(default.fingerprint(), tuned.fingerprint());
+    }
+
+    #[test]
+    fn live_tracks_may_match_live_uploads() {
+        let query = query("Bohemian Rhapsody - Live at Wembley", "Queen", "Live at Wembley");
+        let live = snippet("Queen - Bohemian Rhapsody (Live at Wembley)", "Queen", "");
+
+        assert_eq!(rule_points(&VariantRule(-8.0), &query, &live), 0.0);
+    }
+
+    #[test]
+    fn studio_tracks_penalize_live_uploads() {
+        let query = query("Bohemian Rhapsody", "Queen", "A Night at the Opera");
+        let live = snippet("Queen - Bohemian Rhapsody (Live)", "Queen", "");
+
+        assert_eq!(rule_points(&VariantRule(-8.0), &query, &live), -8.0);
+    }
+
+    #[test]
+    fn penalizes_covers_and_edits() {
+        let query = query("Shape of You", "Ed Sheeran", "Divide");
+        let rule = VariantRule(-8.0);
+
+        for title in [
+            "Shape of You - Ed Sheeran (Acoustic Cover)",
+            "Shape of You (Sped Up)",
+            "Ed Sheeran - Shape of You [8D AUDIO]",
+        ] {
+            assert_eq!(
+                rule_points(&rule, &query, &snippet(title, "Some Channel", "")),
+                -8.0,
+                "{}",
+                title
+            );
+        }
+        // Markers only count as whole words
+        let discovery = snippet("Ed Sheeran - Shape of You (Discovery)", "Ed Sheeran", "");
+        assert_eq!(rule_points(&rule, &query, &discovery), 0.0);
+    }
+}